@@ -3,3 +3,5 @@
 extern crate bitflags;
 pub mod pieces;
 pub mod rules;
+#[cfg(feature = "search")]
+pub mod search;
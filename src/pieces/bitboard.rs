@@ -0,0 +1,386 @@
+//! Module with a bitboard view of the goban, enabled by the `bitboard` feature.
+//!
+//! The stones of each color are kept in masks indexed like the board of the goban, the goban
+//! updates them with its stones. Liberties, captures and flood fills are computed with
+//! operations on whole words instead of walking the points one by one.
+//!
+//! # Example
+//! ```
+//! use goban::pieces::goban::Goban;
+//! use goban::pieces::stones::Color;
+//!
+//! let mut goban = Goban::new((9, 9));
+//! goban.push_many(&[(0, 0), (0, 1)], Color::Black);
+//! goban.push((1, 0), Color::White);
+//! let bitboards = goban.bitboards();
+//! let chain = bitboards.chain((0, 0)).unwrap();
+//! assert_eq!(chain.count(), 2);
+//! assert_eq!(bitboards.liberties(chain).count(), 2);
+//! assert_eq!(bitboards.captures((1, 1), Color::White).count(), 0);
+//! ```
+
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+use crate::pieces::goban::BoardIdx;
+use crate::pieces::stones::{Color, MaybeColor};
+use crate::pieces::util::coord::{one_to_2dim, two_to_1dim, Coord, Size};
+
+/// Number of words of a [`Bitboard`], enough for a 19x19 goban.
+pub const WORDS: usize = 6;
+
+/// Set of points of the goban, one bit per point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard([u64; WORDS]);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard([0; WORDS]);
+
+    /// Set with only the point.
+    #[inline]
+    pub fn single(idx: BoardIdx) -> Self {
+        let mut bitboard = Self::EMPTY;
+        bitboard.set(idx);
+        bitboard
+    }
+
+    #[inline]
+    pub fn get(&self, idx: BoardIdx) -> bool {
+        self.0[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, idx: BoardIdx) {
+        self.0[idx / 64] |= 1 << (idx % 64);
+    }
+
+    #[inline]
+    pub fn clear(&mut self, idx: BoardIdx) {
+        self.0[idx / 64] &= !(1 << (idx % 64));
+    }
+
+    /// Number of points in the set.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    /// Indexes of the points of the set in increasing order.
+    pub fn iter_ones(self) -> impl Iterator<Item = BoardIdx> {
+        self.0
+            .into_iter()
+            .enumerate()
+            .flat_map(|(word_idx, mut word)| {
+                std::iter::from_fn(move || {
+                    if word == 0 {
+                        return None;
+                    }
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    Some(word_idx * 64 + bit)
+                })
+            })
+    }
+
+    /// Moves every point `n` indexes up, `n` must be smaller than 64.
+    #[inline]
+    fn shift_up(self, n: u32) -> Self {
+        Bitboard(std::array::from_fn(|i| {
+            let carry = if i > 0 && n > 0 {
+                self.0[i - 1] >> (64 - n)
+            } else {
+                0
+            };
+            self.0[i] << n | carry
+        }))
+    }
+
+    /// Moves every point `n` indexes down, `n` must be smaller than 64.
+    #[inline]
+    fn shift_down(self, n: u32) -> Self {
+        Bitboard(std::array::from_fn(|i| {
+            let carry = if i + 1 < WORDS && n > 0 {
+                self.0[i + 1] << (64 - n)
+            } else {
+                0
+            };
+            self.0[i] >> n | carry
+        }))
+    }
+}
+
+macro_rules! bitboard_op {
+    ($trait: ident, $fn: ident, $assign_trait: ident, $assign_fn: ident, $op: tt) => {
+        impl $assign_trait for Bitboard {
+            #[inline]
+            fn $assign_fn(&mut self, rhs: Bitboard) {
+                self.0
+                    .iter_mut()
+                    .zip(rhs.0)
+                    .for_each(|(x, y)| x.$assign_fn(y));
+            }
+        }
+
+        impl $trait for Bitboard {
+            type Output = Bitboard;
+
+            #[inline]
+            fn $fn(mut self, rhs: Bitboard) -> Bitboard {
+                self $op rhs;
+                self
+            }
+        }
+    };
+}
+
+bitboard_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &=);
+bitboard_op!(BitOr, bitor, BitOrAssign, bitor_assign, |=);
+bitboard_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^=);
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    /// The complement can have points outside the goban, see [`Bitboards::on_board`].
+    #[inline]
+    fn not(self) -> Bitboard {
+        Bitboard(self.0.map(|word| !word))
+    }
+}
+
+/// Masks of the stones of a goban, and of its points and edges.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bitboards {
+    size: Size,
+    black: Bitboard,
+    white: Bitboard,
+    on_board: Bitboard,
+    first_column: Bitboard,
+    last_column: Bitboard,
+}
+
+impl Bitboards {
+    /// Masks of an empty goban of the size.
+    pub fn new(size: Size) -> Self {
+        let mut on_board = Bitboard::EMPTY;
+        let mut first_column = Bitboard::EMPTY;
+        let mut last_column = Bitboard::EMPTY;
+        for idx in 0..size.0 as usize * size.1 as usize {
+            on_board.set(idx);
+            let (_, column) = one_to_2dim(size, idx);
+            if column == 0 {
+                first_column.set(idx);
+            }
+            if column == size.1 - 1 {
+                last_column.set(idx);
+            }
+        }
+        Bitboards {
+            size,
+            black: Bitboard::EMPTY,
+            white: Bitboard::EMPTY,
+            on_board,
+            first_column,
+            last_column,
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// All the points of the goban.
+    #[inline]
+    pub fn on_board(&self) -> Bitboard {
+        self.on_board
+    }
+
+    #[inline]
+    pub fn stones(&self, color: Color) -> Bitboard {
+        match color {
+            Color::Black => self.black,
+            Color::White => self.white,
+        }
+    }
+
+    /// Points without a stone.
+    #[inline]
+    pub fn empty(&self) -> Bitboard {
+        self.on_board & !(self.black | self.white)
+    }
+
+    #[inline]
+    pub fn get_color(&self, coord: Coord) -> MaybeColor {
+        let idx = two_to_1dim(self.size, coord);
+        if self.black.get(idx) {
+            Some(Color::Black)
+        } else if self.white.get(idx) {
+            Some(Color::White)
+        } else {
+            None
+        }
+    }
+
+    /// Puts the stone on the point, replacing the stone already there like the goban does.
+    #[inline]
+    pub(crate) fn put_stone(&mut self, idx: BoardIdx, color: Color) {
+        self.remove_stone(idx);
+        match color {
+            Color::Black => self.black.set(idx),
+            Color::White => self.white.set(idx),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn remove_stone(&mut self, idx: BoardIdx) {
+        self.black.clear(idx);
+        self.white.clear(idx);
+    }
+
+    /// The set with the points next to it.
+    #[inline]
+    pub fn dilate(&self, set: Bitboard) -> Bitboard {
+        let stride = self.size.1 as u32;
+        let right = set.shift_up(1) & !self.first_column;
+        let left = set.shift_down(1) & !self.last_column;
+        (set | right | left | set.shift_up(stride) | set.shift_down(stride)) & self.on_board
+    }
+
+    /// Points next to the set but not in it.
+    #[inline]
+    pub fn neighbors(&self, set: Bitboard) -> Bitboard {
+        self.dilate(set) & !set
+    }
+
+    /// Points of `within` connected to the points of `seed` in `within`.
+    pub fn flood_fill(&self, seed: Bitboard, within: Bitboard) -> Bitboard {
+        let mut fill = seed & within;
+        loop {
+            let next = self.dilate(fill) & within;
+            if next == fill {
+                return fill;
+            }
+            fill = next;
+        }
+    }
+
+    /// Stones of the chain on the point, None if the point is empty.
+    pub fn chain(&self, coord: Coord) -> Option<Bitboard> {
+        let color = self.get_color(coord)?;
+        let idx = two_to_1dim(self.size, coord);
+        Some(self.flood_fill(Bitboard::single(idx), self.stones(color)))
+    }
+
+    /// Empty points next to the stones.
+    #[inline]
+    pub fn liberties(&self, stones: Bitboard) -> Bitboard {
+        self.neighbors(stones) & self.empty()
+    }
+
+    /// Stones of the opponent captured by a stone of `color` on the empty point.
+    pub fn captures(&self, coord: Coord, color: Color) -> Bitboard {
+        let point = Bitboard::single(two_to_1dim(self.size, coord));
+        let opponent = self.stones(!color);
+        let empty_after = self.empty() & !point;
+        let mut captured = Bitboard::EMPTY;
+        let mut to_check = self.neighbors(point) & opponent;
+        while let Some(idx) = to_check.iter_ones().next() {
+            let chain = self.flood_fill(Bitboard::single(idx), opponent);
+            if (self.neighbors(chain) & empty_after).is_empty() {
+                captured |= chain;
+            }
+            to_check &= !chain;
+        }
+        captured
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pieces::goban::Goban;
+
+    #[test]
+    fn bitboards() {
+        let mut goban = Goban::new((19, 19));
+        goban.push_many(&[(0, 18), (1, 18), (3, 18)], Color::Black);
+        goban.push_many(&[(2, 18), (18, 0), (17, 0)], Color::White);
+        let bitboards = goban.bitboards();
+        assert_eq!(bitboards.get_color((2, 18)), Some(Color::White));
+        assert_eq!(bitboards.stones(Color::Black).count(), 3);
+
+        // The chain on the right edge doesn't leak on the next line
+        let chain = bitboards.chain((0, 18)).unwrap();
+        assert_eq!(chain.count(), 2);
+        let liberties: Vec<Coord> = bitboards
+            .liberties(chain)
+            .iter_ones()
+            .map(|idx| one_to_2dim((19, 19), idx))
+            .collect();
+        assert_eq!(liberties, goban.chain_at((0, 18)).unwrap().liberties());
+
+        let corner = bitboards.chain((18, 0)).unwrap();
+        assert_eq!(bitboards.liberties(corner).count(), 3);
+        assert!(bitboards.captures((17, 1), Color::Black).is_empty());
+        assert!(bitboards.captures((2, 16), Color::White).is_empty());
+
+        // The white stone in atari on the edge is captured
+        let mut goban = Goban::new((19, 19));
+        goban.push_many(&[(1, 18), (3, 18)], Color::Black);
+        goban.push((2, 18), Color::White);
+        let captured = goban.bitboards().captures((2, 17), Color::Black);
+        assert_eq!(captured.iter_ones().collect::<Vec<_>>(), vec![2 * 19 + 18]);
+
+        goban.push((2, 17), Color::Black);
+        goban.remove_chain(goban.get_chain_idx((2, 18)).unwrap());
+        assert_eq!(goban.bitboards().get_color((2, 18)), None);
+        let empty = goban.bitboards().empty();
+        assert_eq!(empty.count(), 19 * 19 - 3);
+        // The point of the captured stone is cut from the other empty points
+        let region = goban.bitboards().flood_fill(Bitboard::single(0), empty);
+        assert_eq!(region, empty ^ Bitboard::single(2 * 19 + 18));
+    }
+
+    #[test]
+    fn non_square_bitboards() {
+        let size = (5, 9);
+        let mut goban = Goban::new(size);
+        // The black wall on the column 4 splits the empty points in two regions
+        goban.push_many(&[(0, 4), (1, 4), (2, 4), (3, 4), (4, 4)], Color::Black);
+        goban.push_many(&[(0, 7), (1, 7)], Color::Black);
+        goban.push_many(&[(0, 8), (1, 8), (4, 0)], Color::White);
+        let bitboards = goban.bitboards();
+        let empty = bitboards.empty();
+        let regions = goban.empty_regions();
+        assert_eq!(regions.len(), 2);
+        for region in regions {
+            let seed = Bitboard::single(two_to_1dim(size, region.points[0]));
+            let fill: Vec<Coord> = bitboards
+                .flood_fill(seed, empty)
+                .iter_ones()
+                .map(|idx| one_to_2dim(size, idx))
+                .collect();
+            assert_eq!(fill, region.points);
+        }
+
+        for coord in [(0, 8), (4, 0), (2, 4)] {
+            let chain = bitboards.chain(coord).unwrap();
+            let mut liberties: Vec<Coord> = bitboards
+                .liberties(chain)
+                .iter_ones()
+                .map(|idx| one_to_2dim(size, idx))
+                .collect();
+            let mut expected = goban.chain_at(coord).unwrap().liberties();
+            liberties.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(liberties, expected);
+        }
+        let captured = bitboards.captures((2, 8), Color::Black);
+        assert_eq!(captured, bitboards.chain((0, 8)).unwrap());
+        assert_eq!(captured.count(), 2);
+    }
+}
@@ -0,0 +1,268 @@
+//! Module with a read only view of the chains of the goban, and the identifiers and events
+//! to follow the chains from a move to another.
+//!
+//! # Example
+//! ```
+//! use goban::pieces::goban::Goban;
+//! use goban::pieces::stones::Color;
+//!
+//! let mut goban = Goban::new((9, 9));
+//! goban.push_many(&[(0, 0), (0, 1)], Color::Black);
+//! goban.push((1, 0), Color::White);
+//! let chain = goban.chain_at((0, 1)).unwrap();
+//! assert_eq!(chain.color(), Color::Black);
+//! assert_eq!(chain.stones().count(), 2);
+//! assert_eq!(chain.liberties(), vec![(0, 2), (1, 1)]);
+//! assert_eq!(chain.adjacent_enemies()[0], goban.chain_at((1, 0)).unwrap());
+//! ```
+
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::pieces::goban::{Goban, GroupIdx};
+use crate::pieces::group::Group;
+use crate::pieces::stones::{Color, Stone};
+use crate::pieces::util::coord::{one_to_2dim, Coord};
+
+/// Identifier of a chain, given when the chain is created. It's kept by the chain until it's
+/// captured or removed, and never given to another chain of the goban.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ChainId(u32);
+
+impl ChainId {
+    #[inline]
+    pub(crate) const fn new(id: u32) -> Self {
+        ChainId(id)
+    }
+
+    #[inline]
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for ChainId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Changes of the chains caused by a stone put on the goban or removed from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChainEvent {
+    /// A new chain with only the stone put.
+    Created(ChainId),
+    /// The stone put was added to the chain.
+    Extended(ChainId),
+    /// The chain `from` was merged in the chain `into` by the stone put, `from` doesn't exist
+    /// anymore. The oldest chain keeps its identifier.
+    Merged { into: ChainId, from: ChainId },
+    /// The chain was captured.
+    Captured(ChainId),
+    /// The chain was removed from the goban without being captured.
+    Removed(ChainId),
+    /// A stone was removed from the chain, the biggest part keeps the identifier.
+    Reduced(ChainId),
+    /// A stone was removed from the chain `from` and cut it, `into` is one of the other parts.
+    Split { from: ChainId, into: ChainId },
+}
+
+/// A chain of the goban, it borrows the goban so it can't outlive a move.
+/// Two views are equal if they are the same chain of the same goban.
+#[derive(Clone, Copy)]
+pub struct ChainRef<'a> {
+    goban: &'a Goban,
+    group: &'a Group,
+    idx: GroupIdx,
+}
+
+impl<'a> ChainRef<'a> {
+    #[inline]
+    pub fn id(&self) -> ChainId {
+        self.group.id
+    }
+
+    #[inline]
+    pub fn color(&self) -> Color {
+        self.group.color
+    }
+
+    /// Number of stones of the chain.
+    #[inline]
+    pub fn num_stones(&self) -> usize {
+        self.group.num_stones as usize
+    }
+
+    /// Coordinates of the stones of the chain.
+    #[inline]
+    pub fn stones(&self) -> impl Iterator<Item = Coord> + 'a {
+        self.goban.chain_stones(self.idx).map(|stone| stone.coord)
+    }
+
+    /// Returns true if the chain has a stone on the point.
+    #[inline]
+    pub fn contains(&self, coord: Coord) -> bool {
+        self.goban.get_chain_idx(coord) == Some(self.idx)
+    }
+
+    /// Coordinates of the liberties of the chain, in row major order.
+    pub fn liberties(&self) -> Vec<Coord> {
+        let size = self.goban.size();
+        self.group
+            .liberties()
+            .into_iter()
+            .map(|idx| one_to_2dim(size, idx))
+            .collect()
+    }
+
+    #[inline]
+    pub fn number_of_liberties(&self) -> usize {
+        self.group.number_of_liberties()
+    }
+
+    /// A chain is in atari if it only has one liberty.
+    #[inline]
+    pub fn is_atari(&self) -> bool {
+        self.group.is_atari()
+    }
+
+    /// Chains of the other color next to the chain.
+    pub fn adjacent_enemies(&self) -> Vec<ChainRef<'a>> {
+        let goban = self.goban;
+        let mut enemies: Vec<ChainRef<'a>> = vec![];
+        for stone in goban.chain_stones(self.idx) {
+            for Stone { coord, color } in goban.get_connected_stones(stone.coord) {
+                if color != self.color() && !enemies.iter().any(|enemy| enemy.contains(coord)) {
+                    enemies.extend(goban.chain_at(coord));
+                }
+            }
+        }
+        enemies
+    }
+}
+
+impl PartialEq for ChainRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.goban, other.goban) && self.idx == other.idx
+    }
+}
+
+impl Eq for ChainRef<'_> {}
+
+impl Debug for ChainRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChainRef")
+            .field("id", &self.id())
+            .field("color", &self.color())
+            .field("stones", &self.stones().collect::<Vec<_>>())
+            .field("liberties", &self.liberties())
+            .finish()
+    }
+}
+
+impl Goban {
+    /// Returns the chain with a stone on the point, None if the point is empty.
+    #[inline]
+    pub fn chain_at(&self, coord: Coord) -> Option<ChainRef<'_>> {
+        let idx = self.get_chain_idx(coord)?;
+        Some(ChainRef {
+            goban: self,
+            group: self.get_chain(coord)?,
+            idx,
+        })
+    }
+
+    /// Returns the chain with this identifier, None if it isn't on the goban anymore.
+    pub fn chain_by_id(&self, id: ChainId) -> Option<ChainRef<'_>> {
+        self.all_chains().find(|chain| chain.id() == id)
+    }
+
+    /// Returns every chain of the goban once, in the row major order of their first stone.
+    pub fn all_chains(&self) -> impl Iterator<Item = ChainRef<'_>> + '_ {
+        let mut seen = vec![];
+        self.get_stones().filter_map(move |stone| {
+            let idx = self.get_chain_idx(stone.coord)?;
+            if seen.contains(&idx) {
+                None
+            } else {
+                seen.push(idx);
+                self.chain_at(stone.coord)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChainEvent::*;
+    use super::ChainId;
+    use crate::pieces::goban::Goban;
+    use crate::pieces::stones::Color;
+
+    #[test]
+    fn chains() {
+        let mut goban = Goban::new((9, 9));
+        goban.push_many(&[(4, 4), (4, 5), (3, 5)], Color::Black);
+        goban.push_many(&[(3, 4), (5, 4)], Color::White);
+        goban.push((0, 0), Color::White);
+
+        let black = goban.chain_at((4, 5)).unwrap();
+        assert_eq!(black, goban.chain_at((3, 5)).unwrap());
+        assert_eq!(black.num_stones(), 3);
+        assert!(black.contains((4, 4)));
+        assert!(!black.contains((3, 4)));
+        assert_eq!(black.number_of_liberties(), 5);
+        assert!(!black.is_atari());
+        let enemies = black.adjacent_enemies();
+        assert_eq!(enemies.len(), 2);
+        assert!(enemies.iter().all(|enemy| enemy.color() == Color::White));
+
+        let corner = goban.chain_at((0, 0)).unwrap();
+        assert_eq!(corner.liberties(), vec![(0, 1), (1, 0)]);
+        assert!(corner.adjacent_enemies().is_empty());
+        assert!(goban.chain_at((8, 8)).is_none());
+        assert_eq!(goban.all_chains().count(), 4);
+    }
+
+    #[test]
+    fn chain_events() {
+        let mut goban = Goban::new((9, 9));
+        goban.push((4, 4), Color::Black);
+        assert_eq!(goban.chain_events(), &[Created(ChainId::new(0))]);
+        goban.push((4, 6), Color::Black);
+        goban.push((4, 5), Color::Black);
+        let first = ChainId::new(0);
+        assert_eq!(
+            goban.chain_events(),
+            &[
+                Extended(first),
+                Merged {
+                    into: first,
+                    from: ChainId::new(1)
+                }
+            ]
+        );
+        assert_eq!(goban.chain_at((4, 6)).unwrap().id(), first);
+
+        goban.push((0, 0), Color::White);
+        goban.push((0, 1), Color::Black);
+        goban.play_stone((1, 0), Color::Black);
+        let events = goban.chain_events();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], Created(_)));
+        assert_eq!(events[1], Captured(ChainId::new(2)));
+        assert!(goban.chain_by_id(ChainId::new(2)).is_none());
+
+        // Removing the stone in the middle cuts the chain in two
+        goban.push((4, 7), Color::Black);
+        goban.remove_stone((4, 5));
+        let events = goban.chain_events();
+        assert_eq!(events[0], Reduced(first));
+        // Only the part cut from the chain takes a new id
+        let into = ChainId::new(5);
+        assert_eq!(events[1], Split { from: first, into });
+        assert_eq!(goban.chain_at((4, 6)).unwrap().id(), first);
+        assert_eq!(goban.chain_at((4, 4)).unwrap().id(), into);
+        goban.push((8, 8), Color::White);
+        assert_eq!(goban.chain_events(), &[Created(ChainId::new(6))]);
+    }
+}
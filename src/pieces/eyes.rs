@@ -0,0 +1,278 @@
+//! Module for the analysis of eyes.
+//!
+//! An eye is an empty region surrounded only by stones of one color, the edges of the goban
+//! aside. A region of one point is a real eye or a false eye, a bigger region is an eye space.
+//!
+//! A point is a real eye if the chains around it are the same chain, or if the opponent doesn't
+//! control too many of its diagonals: one in the center, none on the edges. An empty diagonal
+//! counts as half a diagonal unless it's an eye too. A false eye is still real when the chains
+//! around it are connected by other eyes, like in a two-headed dragon.
+//!
+//! # Example
+//! ```
+//! use goban::pieces::eyes::EyeKind;
+//! use goban::pieces::goban::Goban;
+//! use goban::pieces::stones::Color;
+//!
+//! let mut goban = Goban::new((9, 9));
+//! goban.push_many(&[(0, 1), (1, 0), (1, 1)], Color::White);
+//! assert_eq!(goban.eye_kind((0, 0), Color::White), Some(EyeKind::Real));
+//! goban.remove_stone((1, 1));
+//! goban.push((1, 1), Color::Black);
+//! assert_eq!(goban.eye_kind((0, 0), Color::White), Some(EyeKind::False));
+//! ```
+
+use std::collections::HashMap;
+
+use crate::pieces::chain::ChainId;
+use crate::pieces::goban::{Goban, GroupIdx};
+use crate::pieces::shapes::{canonical_shape, ShapeStatus};
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::{corner_points, is_coord_valid, Coord};
+
+/// Kind of an eye.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EyeKind {
+    /// One point that can't be filled by the opponent.
+    Real,
+    /// One point that the opponent can make the owner fill.
+    False,
+    /// Region of more than one point.
+    Space,
+}
+
+/// An empty region surrounded by the stones of one color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eye {
+    pub kind: EyeKind,
+    /// Color of the stones around.
+    pub color: Color,
+    /// Points of the region.
+    pub points: Vec<Coord>,
+    /// Chains around the region.
+    pub chains: Vec<ChainId>,
+}
+
+impl Eye {
+    /// Number of points of the region.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Points of the region normalized with [`canonical_shape`], two regions with the same shape
+    /// at different places or in different orientations have the same result.
+    #[inline]
+    pub fn shape(&self) -> Vec<Coord> {
+        canonical_shape(&self.points)
+    }
+
+    /// Number of eyes the region is worth: none for a false eye, one for a real eye, two for an
+    /// eye space that lives alone and one for the others. See [`Eye::nakade`].
+    #[inline]
+    pub fn value(&self) -> usize {
+        match self.kind {
+            EyeKind::False => 0,
+            EyeKind::Real => 1,
+            EyeKind::Space if self.nakade().status == ShapeStatus::Alive => 2,
+            EyeKind::Space => 1,
+        }
+    }
+}
+
+impl Goban {
+    /// Returns the kind of eye of `color` on the point, None if it isn't an eye of one point.
+    pub fn eye_kind(&self, coord: Coord, color: Color) -> Option<EyeKind> {
+        if !self.is_eye_shape(coord, color) {
+            return None;
+        }
+        if self.is_local_eye(coord, color) {
+            return Some(EyeKind::Real);
+        }
+        let chains = self.get_connected_groups_idx(coord);
+        // The chains can still be connected by other eyes.
+        let mut components: HashMap<GroupIdx, GroupIdx> =
+            chains.iter().map(|&chain| (chain, chain)).collect();
+        let mut to_explore = chains.to_vec();
+        while let Some(chain) = to_explore.pop() {
+            for liberty in self.chain_liberties(self.chain_stones(chain).next().unwrap().coord) {
+                if liberty == coord || !self.is_eye_shape(liberty, color) {
+                    continue;
+                }
+                let around = self.get_connected_groups_idx(liberty);
+                let component = around
+                    .iter()
+                    .filter_map(|chain| components.get(chain).copied())
+                    .min()
+                    .unwrap();
+                for chain in around {
+                    match components.insert(chain, component) {
+                        None => to_explore.push(chain),
+                        Some(old) if old != component => components
+                            .values_mut()
+                            .filter(|c| **c == old)
+                            .for_each(|c| *c = component),
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        let first = components[&chains[0]];
+        if chains.iter().all(|chain| components[chain] == first) {
+            Some(EyeKind::Real)
+        } else {
+            Some(EyeKind::False)
+        }
+    }
+
+    /// Returns true if the point is a real eye of `color` looking only at the point, its chains
+    /// and its diagonals. It's cheap enough for the legality of the moves, but the chains
+    /// connected by other eyes, like in a two-headed dragon, are missed: see
+    /// [`Goban::eye_kind`].
+    #[inline]
+    pub fn is_local_eye(&self, coord: Coord, color: Color) -> bool {
+        self.is_eye_shape(coord, color)
+            && (self.get_connected_groups_idx(coord).len() == 1
+                || self.diagonals_held(coord, color))
+    }
+
+    /// Returns all the eyes of `color`: real and false eyes of one point and eye spaces.
+    pub fn eyes(&self, color: Color) -> Vec<Eye> {
+        self.empty_regions()
+            .into_iter()
+            .filter(|region| region.owner() == Some(color))
+            .map(|region| Eye {
+                kind: if region.size() == 1 {
+                    self.eye_kind(region.points[0], color).unwrap()
+                } else {
+                    EyeKind::Space
+                },
+                color,
+                points: region.points,
+                chains: region.chains,
+            })
+            .collect()
+    }
+
+    /// Returns the eyes around the chain on the point.
+    pub fn chain_eyes(&self, coord: Coord) -> Vec<Eye> {
+        let Some(chain) = self.get_chain(coord) else {
+            return vec![];
+        };
+        self.eyes(chain.color)
+            .into_iter()
+            .filter(|eye| eye.chains.contains(&chain.id))
+            .collect()
+    }
+
+    /// Returns the number of eyes of the chain on the point. See [`Eye::value`].
+    #[inline]
+    pub fn count_eyes(&self, coord: Coord) -> usize {
+        self.chain_eyes(coord).iter().map(Eye::value).sum()
+    }
+
+    /// The point is empty and its neighbors are stones of `color`.
+    fn is_eye_shape(&self, coord: Coord, color: Color) -> bool {
+        self.get_color(coord).is_none()
+            && self
+                .get_connected_points(coord)
+                .all(|p| p.color == Some(color))
+    }
+
+    /// The opponent doesn't hold too many diagonals of the point.
+    fn diagonals_held(&self, coord: Coord, color: Color) -> bool {
+        let mut on_edge = false;
+        // In half diagonals
+        let mut opponent = 0;
+        for corner in corner_points(coord) {
+            if !is_coord_valid(self.size(), corner) {
+                on_edge = true;
+                continue;
+            }
+            match self.get_color(corner) {
+                Some(c) if c != color => opponent += 2,
+                None if !self.is_eye_shape(corner, color) => opponent += 1,
+                _ => {}
+            }
+        }
+        if on_edge {
+            opponent < 2
+        } else {
+            opponent < 4
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eyes() {
+        let mut goban = Goban::new((9, 9));
+        // White group with a real eye in the corner and a false eye on the edge.
+        goban.push_many(
+            &[(0, 1), (1, 0), (1, 1), (1, 2), (0, 3), (1, 4), (0, 5)],
+            Color::White,
+        );
+        goban.push_many(&[(1, 3), (1, 5), (2, 4)], Color::Black);
+        assert_eq!(goban.eye_kind((0, 0), Color::White), Some(EyeKind::Real));
+        assert_eq!(goban.eye_kind((0, 2), Color::White), Some(EyeKind::False));
+        assert_eq!(goban.eye_kind((0, 4), Color::White), Some(EyeKind::False));
+        assert_eq!(goban.eye_kind((0, 0), Color::Black), None);
+        assert_eq!(goban.count_eyes((1, 1)), 1);
+
+        // Eye space of two points
+        let mut goban = Goban::new((9, 9));
+        goban.push((8, 8), Color::White);
+        goban.push_many(
+            &[(0, 3), (1, 0), (1, 1), (1, 2), (1, 3), (2, 0), (0, 0)],
+            Color::Black,
+        );
+        let eyes = goban.eyes(Color::Black);
+        assert_eq!(eyes.len(), 1);
+        assert_eq!(eyes[0].kind, EyeKind::Space);
+        assert_eq!(eyes[0].size(), 2);
+        assert_eq!(eyes[0].shape(), vec![(0, 0), (0, 1)]);
+        assert!(goban.eyes(Color::White).is_empty());
+    }
+
+    #[test]
+    fn two_headed_dragon() {
+        let mut goban = Goban::new((9, 9));
+        // Two white chains sharing two false eyes on the edge.
+        goban.push_many(
+            &[
+                (0, 0),
+                (1, 0),
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (3, 2),
+                (3, 3),
+                (3, 4),
+                (3, 5),
+                (2, 5),
+                (1, 5),
+                (1, 6),
+                (0, 6),
+            ],
+            Color::White,
+        );
+        goban.push_many(&[(0, 2), (0, 3), (0, 4)], Color::White);
+        goban.push_many(
+            &[(1, 2), (1, 3), (1, 4), (2, 2), (2, 4), (8, 8)],
+            Color::Black,
+        );
+        assert_eq!(goban.eye_kind((0, 1), Color::White), Some(EyeKind::Real));
+        assert_eq!(goban.eye_kind((0, 5), Color::White), Some(EyeKind::Real));
+        // The local check doesn't see the other eye
+        assert!(!goban.is_local_eye((0, 1), Color::White));
+        assert_eq!(goban.count_eyes((0, 3)), 2);
+        assert_eq!(goban.count_eyes((3, 3)), 2);
+
+        goban.remove_stone((0, 6));
+        assert_eq!(goban.eye_kind((0, 1), Color::White), Some(EyeKind::False));
+        assert_eq!(goban.count_eyes((0, 3)), 0);
+    }
+}
@@ -1,6 +1,9 @@
 //! Module with the goban and his implementations.
 
 use crate::one2dim;
+#[cfg(feature = "bitboard")]
+use crate::pieces::bitboard::Bitboards;
+use crate::pieces::chain::{ChainEvent, ChainId};
 use crate::pieces::group::CircularGroupIter;
 use crate::pieces::group::{merge, set, Group, Groups, Liberties, EMPTY_LIBERTIES};
 use crate::pieces::stones::*;
@@ -15,6 +18,7 @@ use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 pub type GroupIdx = usize;
 pub type BoardIdx = usize;
@@ -22,8 +26,12 @@ pub type BoardIdx = usize;
 const BOARD_MAX_SIZE: (Nat, Nat) = (19, 19);
 const BOARD_MAX_LENGTH: usize = BOARD_MAX_SIZE.0 as usize * BOARD_MAX_SIZE.1 as usize;
 const MAX_CHAINS: usize = 4 * BOARD_MAX_LENGTH / 5;
+/// A stone touches at most 4 chains, it can merge or capture them and kill its own chain.
+const MAX_CHAIN_EVENTS: usize = 6;
 
 /// Represents a goban. the stones are stored in ROW MAJOR (row, column)
+///
+/// A goban is `Send` and `Sync`, it has no shared state.
 #[derive(Debug, Clone, Eq)]
 pub struct Goban {
     chains: Groups,
@@ -32,8 +40,19 @@ pub struct Goban {
     next_stone: Vec<u16>,
     size: Size,
     zobrist_hash: u64,
+    /// Second hash of the stones with wider keys, for the super ko and the transposition tables.
+    wide_hash: Option<RunningHash<u128>>,
+    next_chain_id: u32,
+    chain_events: ArrayVec<ChainEvent, MAX_CHAIN_EVENTS>,
+    #[cfg(feature = "bitboard")]
+    bitboards: Bitboards,
 }
 
+const _: () = {
+    fn assert<T: Send + Sync>() {}
+    let _ = assert::<Goban>;
+};
+
 impl From<&[MaybeColor]> for Goban {
     fn from(stones: &[MaybeColor]) -> Self {
         let size = (stones.len() as f32).sqrt() as u8;
@@ -63,9 +82,14 @@ impl Goban {
         Goban {
             size: (height, width),
             zobrist_hash: 0,
+            wide_hash: None,
             board: vec![None; BOARD_MAX_LENGTH],
             next_stone: vec![0; BOARD_MAX_LENGTH],
             chains: Groups::with_capacity(MAX_CHAINS),
+            next_chain_id: 0,
+            chain_events: ArrayVec::new_const(),
+            #[cfg(feature = "bitboard")]
+            bitboards: Bitboards::new((height, width)),
             //free_slots: Default::default(),
         }
     }
@@ -78,6 +102,41 @@ impl Goban {
         self.zobrist_hash
     }
 
+    /// Hash of the stones with the 128 bits keys set by [`Goban::set_wide_keys`], kept up to
+    /// date like [`Goban::zobrist_hash`]. None if no keys were set.
+    #[inline]
+    pub fn wide_hash(&self) -> Option<u128> {
+        self.wide_hash.as_ref().map(RunningHash::hash)
+    }
+
+    #[inline]
+    pub fn wide_keys(&self) -> Option<&Arc<ZobristKeys<u128>>> {
+        self.wide_hash.as_ref().map(RunningHash::keys)
+    }
+
+    /// Keeps a second hash of the stones with the keys, less likely to collide than the
+    /// 64 bits hash.
+    ///
+    /// # Panics
+    /// If the keys are for another size.
+    pub fn set_wide_keys(&mut self, keys: Arc<ZobristKeys<u128>>) {
+        self.wide_hash = Some(RunningHash::new(keys, self));
+    }
+
+    /// Changes of the chains caused by the last stone put on the goban or removed from it,
+    /// with the chains it captured.
+    #[inline]
+    pub fn chain_events(&self) -> &[ChainEvent] {
+        &self.chain_events
+    }
+
+    /// Masks of the stones of the goban, kept up to date with the stones.
+    #[cfg(feature = "bitboard")]
+    #[inline]
+    pub fn bitboards(&self) -> &Bitboards {
+        &self.bitboards
+    }
+
     // Returns all the groups in the goban even dead groups
     pub fn chains(&self) -> impl Iterator<Item = &Group> {
         self.chains.iter()
@@ -155,6 +214,7 @@ impl Goban {
         color: Color,
     ) -> (ArrayVec<usize, 4>, GroupIdx) {
         let pushed_stone_idx = two_to_1dim(self.size, point);
+        self.chain_events.clear();
 
         let mut adjacent_same_color_groups = ArrayVec::<BoardIdx, 4>::new();
         let mut adjacent_opposite_color_groups = ArrayVec::<BoardIdx, 4>::new();
@@ -192,9 +252,17 @@ impl Goban {
         let number_of_neighbors_strings = adjacent_same_color_groups.len();
 
         let updated_ren_index = match number_of_neighbors_strings {
-            0 => self.create_chain(pushed_stone_idx, color, &liberties),
+            0 => {
+                let new_ren_idx = self.create_chain(pushed_stone_idx, color, &liberties);
+                let id = self.new_chain_id();
+                self.chains[new_ren_idx].id = id;
+                self.chain_events.push(ChainEvent::Created(id));
+                new_ren_idx
+            }
             1 => {
                 let only_ren_idx = adjacent_same_color_groups[0];
+                self.chain_events
+                    .push(ChainEvent::Extended(self.chains[only_ren_idx].id));
 
                 self.chains[only_ren_idx]
                     .remove_liberty(pushed_stone_idx)
@@ -204,6 +272,17 @@ impl Goban {
                 only_ren_idx
             }
             _ => {
+                // The oldest chain keeps its id
+                let mut ids: ArrayVec<ChainId, 4> = adjacent_same_color_groups
+                    .iter()
+                    .map(|&ren_idx| self.chains[ren_idx].id)
+                    .collect();
+                ids.sort_unstable();
+                self.chain_events.push(ChainEvent::Extended(ids[0]));
+                for &from in &ids[1..] {
+                    self.chain_events
+                        .push(ChainEvent::Merged { into: ids[0], from });
+                }
                 let mut to_merge = self.create_chain(pushed_stone_idx, color, &liberties);
                 for adj_ren in adjacent_same_color_groups {
                     if self.chains[adj_ren].number_of_liberties()
@@ -216,20 +295,30 @@ impl Goban {
                     }
                 }
                 self.chains[to_merge].remove_liberty(pushed_stone_idx);
+                self.chains[to_merge].id = ids[0];
                 to_merge
             }
         };
         self.zobrist_hash ^= index_zobrist(pushed_stone_idx, color);
+        if let Some(wide_hash) = &mut self.wide_hash {
+            wide_hash.toggle(pushed_stone_idx, color);
+        }
+        #[cfg(feature = "bitboard")]
+        self.bitboards.put_stone(pushed_stone_idx, color);
         #[cfg(debug_assertions)]
         self.check_integrity_all();
         (dead_groups, updated_ren_index)
     }
 
+    /// Removes the dead groups after a push, the removed stones are added to `captured` if given.
+    /// # Returns
+    /// A tuple with ((stones captured by black, stones captured by white), the ko point)
     pub(crate) fn remove_captured_stones_aux(
         &mut self,
         suicide_allowed: bool,
         dead_groups_indices: &[GroupIdx],
         added_group: GroupIdx,
+        mut captured: Option<&mut Vec<Stone>>,
     ) -> ((u32, u32), Option<Coord>) {
         let only_one_ren_removed = dead_groups_indices.len() == 1;
         let mut stones_removed = (0, 0);
@@ -248,7 +337,12 @@ impl Goban {
                     stones_removed.1 += dead_chain.num_stones as u32;
                 }
             }
-            self.remove_chain(dead_ren_idx);
+            if let Some(captured) = captured.as_deref_mut() {
+                captured.extend(self.chain_stones(dead_ren_idx));
+            }
+            self.chain_events
+                .push(ChainEvent::Captured(self.chains[dead_ren_idx].id));
+            self.remove_chain_stones(dead_ren_idx);
         }
 
         let maybe_dead_chain = &self.chains[added_group];
@@ -262,7 +356,12 @@ impl Goban {
                 }
             }
             ko_point = None;
-            self.remove_chain(added_group);
+            if let Some(captured) = captured {
+                captured.extend(self.chain_stones(added_group));
+            }
+            self.chain_events
+                .push(ChainEvent::Captured(self.chains[added_group].id));
+            self.remove_chain_stones(added_group);
         }
         (stones_removed, ko_point)
     }
@@ -319,6 +418,32 @@ impl Goban {
             .filter_map(|x| x.try_into().ok())
     }
 
+    /// Hashes of the goban after the stone is put and the chains in atari around it are
+    /// captured, (64 bits hash, 128 bits hash). Nothing is played, so they cost a few xors
+    /// instead of a copy of the goban.
+    pub(crate) fn hashes_after(&self, stone: Stone) -> (u64, Option<u128>) {
+        let wide_keys = self.wide_keys();
+        let mut hash = self.zobrist_hash;
+        let mut wide_hash = self.wide_hash();
+        let mut toggle = |idx: BoardIdx, color: Color| {
+            hash ^= index_zobrist(idx, color);
+            if let (Some(keys), Some(wide_hash)) = (wide_keys, &mut wide_hash) {
+                *wide_hash ^= keys.stone_idx(idx, color);
+            }
+        };
+        let stone_idx = two_to_1dim(self.size, stone.coord);
+        toggle(stone_idx, stone.color);
+        for chain_idx in self.get_connected_groups_idx(stone_idx) {
+            let chain = &self.chains[chain_idx];
+            if chain.color != stone.color && chain.is_atari() {
+                for idx in chain.iter(&self.next_stone) {
+                    toggle(idx, chain.color);
+                }
+            }
+        }
+        (hash, wide_hash)
+    }
+
     /// Get all connected groups to the coordinate.
     pub(crate) fn get_connected_groups(&self, index: impl IntoIdx) -> Connections<&Group> {
         self.get_connected_groups_idx(index)
@@ -348,6 +473,19 @@ impl Goban {
         self.board[idx].map(|chain_id| self.chains[chain_id.get() as usize].color)
     }
 
+    /// Returns the chain with a stone on the point, None if the point is empty.
+    #[inline]
+    pub fn get_chain(&self, coord: impl IntoIdx) -> Option<&Group> {
+        self.get_chain_idx(coord)
+            .map(|chain_idx| &self.chains[chain_idx])
+    }
+
+    #[inline]
+    pub(crate) fn get_chain_idx(&self, coord: impl IntoIdx) -> Option<GroupIdx> {
+        let idx = coord.into_idx(self.size);
+        self.board[idx].map(|chain_idx| chain_idx.get() as usize)
+    }
+
     /// Get all the stones except "EMPTY stones"
     #[inline]
     pub fn get_stones(&self) -> impl Iterator<Item = Stone> + '_ {
@@ -454,6 +592,13 @@ impl Goban {
     /// Remove a string from the game, it adds liberties to all
     /// adjacent chains that are different color.
     pub fn remove_chain(&mut self, ren_to_remove_idx: GroupIdx) {
+        self.chain_events.clear();
+        self.chain_events
+            .push(ChainEvent::Removed(self.chains[ren_to_remove_idx].id));
+        self.remove_chain_stones(ren_to_remove_idx);
+    }
+
+    fn remove_chain_stones(&mut self, ren_to_remove_idx: GroupIdx) {
         let chain = self.chains[ren_to_remove_idx];
         let color_of_the_string = chain.color;
         for point_idx in chain.iter(&self.next_stone) {
@@ -465,11 +610,60 @@ impl Goban {
                 self.chains[n].add_liberty(point_idx);
             }
             self.zobrist_hash ^= index_zobrist(point_idx, color_of_the_string);
+            if let Some(wide_hash) = &mut self.wide_hash {
+                wide_hash.toggle(point_idx, color_of_the_string);
+            }
+            #[cfg(feature = "bitboard")]
+            self.bitboards.remove_stone(point_idx);
             self.board[point_idx] = None;
         }
         self.chains.remove(ren_to_remove_idx);
     }
 
+    /// Removes one stone from the goban, the other stones of his chain stay on the goban.
+    /// The biggest part of the chain keeps its id, the other parts get new ones.
+    /// Returns the color of the removed stone, None if the point was empty.
+    pub fn remove_stone(&mut self, coord: Coord) -> MaybeColor {
+        let stone_idx = two_to_1dim(self.size, coord);
+        let chain_idx = self.board[stone_idx]?.get() as usize;
+        let color = self.chains[chain_idx].color;
+        let id = self.chains[chain_idx].id;
+        let others: Vec<BoardIdx> = self
+            .iter_stones(chain_idx)
+            .filter(|&idx| idx != stone_idx)
+            .collect();
+        // The chain can be split by the removal, so the other stones are put back one by one.
+        // The ids taken by the stones put back are given back, only the split parts get new ones.
+        let next_chain_id = self.next_chain_id;
+        self.remove_chain_stones(chain_idx);
+        for &idx in &others {
+            self.push_wth_feedback(one_to_2dim(self.size, idx), color);
+        }
+        self.next_chain_id = next_chain_id;
+        let mut parts: Vec<GroupIdx> = vec![];
+        for idx in others {
+            let part = self.board[idx].unwrap().get() as usize;
+            if !parts.contains(&part) {
+                parts.push(part);
+            }
+        }
+        parts.sort_by_key(|&part| std::cmp::Reverse(self.chains[part].num_stones));
+        self.chain_events.clear();
+        match parts.split_first() {
+            None => self.chain_events.push(ChainEvent::Removed(id)),
+            Some((&biggest, others)) => {
+                self.chains[biggest].id = id;
+                self.chain_events.push(ChainEvent::Reduced(id));
+                for &part in others {
+                    let into = self.new_chain_id();
+                    self.chains[part].id = into;
+                    self.chain_events.push(ChainEvent::Split { from: id, into });
+                }
+            }
+        }
+        Some(color)
+    }
+
     /// Updates the group idx of the board when merging groups
     fn update_chain_indexes_in_board(&mut self, chain_idx: GroupIdx) {
         debug_assert_eq!(
@@ -491,12 +685,18 @@ impl Goban {
     }
 
     #[inline]
-    fn neighbors_idx(&self, board_idx: BoardIdx) -> impl Iterator<Item = BoardIdx> {
+    pub(crate) fn neighbors_idx(&self, board_idx: BoardIdx) -> impl Iterator<Item = BoardIdx> {
         let size = self.size;
         self.neighbors_coords(one_to_2dim(size, board_idx))
             .map(move |coord| two_to_1dim(size, coord))
     }
 
+    #[inline]
+    fn new_chain_id(&mut self) -> ChainId {
+        self.next_chain_id += 1;
+        ChainId::new(self.next_chain_id - 1)
+    }
+
     #[inline]
     fn create_chain(&mut self, origin: BoardIdx, color: Color, liberties: &[BoardIdx]) -> GroupIdx {
         let mut lib_bitset: Liberties = EMPTY_LIBERTIES;
@@ -608,6 +808,14 @@ impl Goban {
         for (ren_idx, _) in self.chains.iter_with_index() {
             self.check_integrity_group(ren_idx);
         }
+        #[cfg(feature = "bitboard")]
+        for idx in 0..self.size.0 as usize * self.size.1 as usize {
+            assert_eq!(
+                self.bitboards.get_color(one_to_2dim(self.size, idx)),
+                self.get_color(idx),
+                "The bitboards don't match the board"
+            );
+        }
     }
 }
 
@@ -651,6 +859,7 @@ impl PartialEq for Goban {
 mod tests {
     use super::Goban;
     use crate::pieces::stones::{Color, Stone};
+    use crate::pieces::util::coord::{one_to_2dim, two_to_1dim};
     use crate::rules::GobanSizes;
 
     #[test]
@@ -702,4 +911,32 @@ mod tests {
 
         assert_eq!(goban.get_liberties(s.coord).count(), 0);
     }
+
+    #[test]
+    fn non_square_indexing() {
+        // 5 lines of 9 columns, the lines used to overlap
+        let size = (5, 9);
+        for line in 0..5 {
+            for column in 0..9 {
+                let index = two_to_1dim(size, (line, column));
+                assert!(index < 45);
+                assert_eq!(one_to_2dim(size, index), (line, column));
+            }
+        }
+        let mut goban = Goban::new(size);
+        goban.push((1, 0), Color::Black);
+        goban.push((0, 5), Color::White);
+        goban.push((4, 8), Color::Black);
+        assert_eq!(goban.get_color((1, 0)), Some(Color::Black));
+        assert_eq!(goban.get_color((0, 5)), Some(Color::White));
+        assert_eq!(goban.get_color((4, 8)), Some(Color::Black));
+        assert_eq!(goban.get_stones().count(), 3);
+        assert_eq!(goban.get_empty_coords().count(), 42);
+        let mut liberties: Vec<_> = goban.get_liberties((4, 8)).collect();
+        liberties.sort();
+        assert_eq!(liberties, vec![(3, 8), (4, 7)]);
+        assert_eq!(goban.get_liberties((0, 5)).count(), 3);
+        assert_eq!(goban.matrix()[1][0], Some(Color::Black));
+        assert_eq!(goban.matrix()[4][8], Some(Color::Black));
+    }
 }
@@ -0,0 +1,212 @@
+//! Module with the common fate graph of the goban.
+//!
+//! Each chain and each maximal empty region of the goban is a node, two nodes are linked if
+//! a point of one is next to a point of the other. The stones of a chain share the same fate,
+//! so the graph is a smaller view of the goban for the analysis of life and death and
+//! semeais.
+//!
+//! # Example
+//! ```
+//! use goban::pieces::goban::Goban;
+//! use goban::pieces::stones::Color;
+//!
+//! let mut goban = Goban::new((5, 5));
+//! goban.push_many(&[(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)], Color::Black);
+//! let graph = goban.common_fate_graph();
+//! // The black wall cuts the goban in two empty regions
+//! assert_eq!(graph.node_count(), 3);
+//! let wall = graph.node_at((0, 2)).unwrap();
+//! assert_eq!(graph.neighbors(wall).len(), 2);
+//! assert_eq!(graph.node(wall).liberties, 10);
+//! ```
+
+use crate::pieces::chain::ChainId;
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::MaybeColor;
+use crate::pieces::util::coord::{Coord, Size};
+
+/// Index of a node in a [`CommonFateGraph`].
+pub type NodeIdx = usize;
+
+/// A chain or an empty region of the goban.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FateNode {
+    /// Color of the chain, None for an empty region.
+    pub color: MaybeColor,
+    /// Identifier of the chain, None for an empty region.
+    pub chain: Option<ChainId>,
+    /// Stones of the chain or points of the empty region.
+    pub points: Vec<Coord>,
+    /// Number of liberties of the chain, 0 for an empty region.
+    pub liberties: usize,
+}
+
+impl FateNode {
+    /// Number of points of the node.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    #[inline]
+    pub fn is_chain(&self) -> bool {
+        self.color.is_some()
+    }
+}
+
+/// Graph of the chains and the empty regions of a goban, built by
+/// [`Goban::common_fate_graph`]. It doesn't follow the changes of the goban.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommonFateGraph {
+    size: Size,
+    nodes: Vec<FateNode>,
+    neighbors: Vec<Vec<NodeIdx>>,
+    /// Node of each point in row major order.
+    node_of_point: Vec<NodeIdx>,
+}
+
+impl CommonFateGraph {
+    #[inline]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Number of links between the nodes.
+    #[inline]
+    pub fn edge_count(&self) -> usize {
+        self.neighbors.iter().map(Vec::len).sum::<usize>() / 2
+    }
+
+    #[inline]
+    pub fn nodes(&self) -> &[FateNode] {
+        &self.nodes
+    }
+
+    /// # Panics
+    /// If the node isn't in the graph.
+    #[inline]
+    pub fn node(&self, idx: NodeIdx) -> &FateNode {
+        &self.nodes[idx]
+    }
+
+    /// Nodes linked to the node, in increasing order.
+    #[inline]
+    pub fn neighbors(&self, idx: NodeIdx) -> &[NodeIdx] {
+        &self.neighbors[idx]
+    }
+
+    /// All the links, each one once with the smallest node first.
+    pub fn edges(&self) -> impl Iterator<Item = (NodeIdx, NodeIdx)> + '_ {
+        self.neighbors
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, neighbors)| {
+                neighbors
+                    .iter()
+                    .filter(move |&&other| idx < other)
+                    .map(move |&other| (idx, other))
+            })
+    }
+
+    /// Node with the point, None if the point is outside the goban.
+    #[inline]
+    pub fn node_at(&self, (line, column): Coord) -> Option<NodeIdx> {
+        if line < self.size.0 && column < self.size.1 {
+            Some(self.node_of_point[line as usize * self.size.1 as usize + column as usize])
+        } else {
+            None
+        }
+    }
+}
+
+impl Goban {
+    /// Builds the common fate graph of the goban, the chains come first in the nodes, then the
+    /// empty regions.
+    pub fn common_fate_graph(&self) -> CommonFateGraph {
+        let size = self.size();
+        let width = size.1 as usize;
+        let point_idx = |(line, column): Coord| line as usize * width + column as usize;
+        let mut node_of_point = vec![0; size.0 as usize * width];
+        let mut nodes = vec![];
+
+        for chain in self.all_chains() {
+            let points: Vec<Coord> = chain.stones().collect();
+            for &point in &points {
+                node_of_point[point_idx(point)] = nodes.len();
+            }
+            nodes.push(FateNode {
+                color: Some(chain.color()),
+                chain: Some(chain.id()),
+                points,
+                liberties: chain.number_of_liberties(),
+            });
+        }
+        for region in self.empty_regions() {
+            for &point in &region.points {
+                node_of_point[point_idx(point)] = nodes.len();
+            }
+            nodes.push(FateNode {
+                color: None,
+                chain: None,
+                points: region.points,
+                liberties: 0,
+            });
+        }
+
+        let mut neighbors = vec![vec![]; nodes.len()];
+        for (idx, node) in nodes.iter().enumerate() {
+            for &point in &node.points {
+                for neighbor in self.get_connected_points(point) {
+                    let other = node_of_point[point_idx(neighbor.coord)];
+                    if other != idx {
+                        neighbors[idx].push(other);
+                    }
+                }
+            }
+            neighbors[idx].sort_unstable();
+            neighbors[idx].dedup();
+        }
+
+        CommonFateGraph {
+            size,
+            nodes,
+            neighbors,
+            node_of_point,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pieces::goban::Goban;
+    use crate::pieces::stones::Color;
+
+    #[test]
+    fn common_fate_graph() {
+        let mut goban = Goban::new((5, 5));
+        goban.push_many(&[(0, 1), (1, 0), (1, 1)], Color::Black);
+        goban.push_many(&[(2, 0), (2, 1), (2, 2), (1, 2), (0, 2)], Color::White);
+        let graph = goban.common_fate_graph();
+        // Black chain, white chain, the corner and the rest of the goban
+        assert_eq!(graph.node_count(), 4);
+        let black = graph.node_at((1, 1)).unwrap();
+        let white = graph.node_at((0, 2)).unwrap();
+        let corner = graph.node_at((0, 0)).unwrap();
+        let rest = graph.node_at((4, 4)).unwrap();
+        assert_eq!(graph.node(black).size(), 3);
+        assert_eq!(graph.node(black).liberties, 1);
+        assert_eq!(graph.node(white).color, Some(Color::White));
+        assert!(!graph.node(corner).is_chain());
+        assert_eq!(graph.node(corner).points, vec![(0, 0)]);
+        assert_eq!(graph.neighbors(corner), &[black]);
+        assert_eq!(graph.neighbors(black).len(), 2);
+        assert_eq!(graph.neighbors(rest), &[white]);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.edges().count(), 3);
+        assert_eq!(graph.node_at((5, 0)), None);
+        assert_eq!(
+            graph.node(white).chain,
+            Some(goban.chain_at((2, 0)).unwrap().id())
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::ops::{BitOrAssign, Index, IndexMut};
 
+use crate::pieces::chain::ChainId;
 use crate::pieces::goban::GroupIdx;
 use crate::pieces::stones::Color;
 use crate::pieces::BoardIdx;
@@ -40,11 +41,11 @@ fn any(lib: &Liberties) -> bool {
 }
 
 #[inline(always)]
-fn count_ones(lib: &Liberties) -> usize {
+pub(crate) fn count_ones(lib: &Liberties) -> usize {
     lib.iter().map(|x| x.count_ones() as usize).sum()
 }
 
-fn iter_ones(lib: &Liberties) -> impl Iterator<Item = usize> + '_ {
+pub(crate) fn iter_ones(lib: &Liberties) -> impl Iterator<Item = usize> + '_ {
     lib.iter().enumerate().flat_map(|(ix, chunk)| {
         let mut chunk = *chunk;
         let mut ixs = ArrayVec::<usize, BITS>::new();
@@ -59,7 +60,8 @@ fn iter_ones(lib: &Liberties) -> impl Iterator<Item = usize> + '_ {
     })
 }
 
-fn get(index: usize, lib: &Liberties) -> bool {
+#[inline(always)]
+pub(crate) fn get(index: usize, lib: &Liberties) -> bool {
     let chunk = index / BITS;
     let bit_index = index % BITS;
     (lib[chunk] & (1 << bit_index)) != 0
@@ -67,6 +69,8 @@ fn get(index: usize, lib: &Liberties) -> bool {
 
 #[derive(Clone, Debug, PartialEq, Eq, Copy, Hash)]
 pub struct Group {
+    /// Identifier of the chain, it doesn't change while the chain is on the goban.
+    pub id: ChainId,
     pub color: Color,
     pub origin: u16,
     pub last: u16,
@@ -82,6 +86,7 @@ impl Group {
 
     pub fn new_with_liberties(color: Color, stone: BoardIdx, liberties: Liberties) -> Self {
         Group {
+            id: ChainId::default(),
             color,
             origin: stone as u16,
             last: stone as u16,
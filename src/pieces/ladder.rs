@@ -0,0 +1,368 @@
+//! Module for reading ladders (shicho) on the goban.
+//!
+//! The reading only follows the moves of a ladder: the attacker puts the chain in atari,
+//! the defender extends or captures a chain of the attacker in atari.
+//! The number of positions read is bounded by a budget, when it's exhausted the chain is
+//! considered escaped.
+//!
+//! # Example
+//! ```
+//! use goban::pieces::goban::Goban;
+//! use goban::pieces::stones::Color;
+//!
+//! let mut goban = Goban::new((9, 9));
+//! goban.push((4, 4), Color::White);
+//! goban.push_many(&[(3, 4), (4, 3), (5, 4), (5, 5)], Color::Black);
+//! let ladder = goban.read_ladder((4, 4)).unwrap();
+//! assert!(ladder.captured);
+//!
+//! // A white stone on the path breaks the ladder
+//! goban.push((1, 7), Color::White);
+//! let ladder = goban.read_ladder((4, 4)).unwrap();
+//! assert!(!ladder.captured);
+//! assert!(ladder.breakers.iter().any(|s| s.coord == (1, 7)));
+//! ```
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::{Color, Stone};
+use crate::pieces::util::coord::{one_to_2dim, Coord};
+
+/// Default number of positions read in a ladder.
+pub const LADDER_BUDGET: u32 = 1000;
+
+/// Result of the reading of a ladder.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Ladder {
+    /// True if the chain is captured.
+    pub captured: bool,
+    /// Main line of the ladder. If the chain is captured, it's the line where the defender
+    /// extends and the attacker answers with the moves that capture.
+    pub path: Vec<Stone>,
+    /// Stones that make the ladder fail: the stones the defender connects to, and the stones
+    /// of the attacker he captures.
+    pub breakers: Vec<Stone>,
+    /// True if the budget was exhausted, the chain is then considered escaped.
+    pub budget_exhausted: bool,
+}
+
+struct LadderReader {
+    budget: u32,
+    exhausted: bool,
+    path: Vec<Stone>,
+    breakers: Vec<Stone>,
+}
+
+impl Goban {
+    /// Plays a stone and removes the captured chains.
+    /// Returns false if the move is a suicide, then the goban must not be used anymore.
+    pub(crate) fn play_stone(&mut self, coord: Coord, color: Color) -> bool {
+        let (dead_chains, added_chain) = self.push_wth_feedback(coord, color);
+        self.remove_captured_stones_aux(false, &dead_chains, added_chain, None);
+        !self.get_chain(coord).is_some_and(|chain| chain.is_dead())
+    }
+
+    /// Liberties of the chain on the point.
+    pub(crate) fn chain_liberties(&self, coord: Coord) -> Vec<Coord> {
+        self.get_chain(coord)
+            .map(|chain| {
+                chain
+                    .liberties()
+                    .into_iter()
+                    .map(|idx| one_to_2dim(self.size(), idx))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Reads the ladder of the chain on the point with the default budget.
+    /// If the chain is in atari the defender moves first, if it has two liberties the attacker
+    /// moves first. None if the point is empty or the chain has more liberties.
+    #[inline]
+    pub fn read_ladder(&self, coord: Coord) -> Option<Ladder> {
+        self.read_ladder_with_budget(coord, LADDER_BUDGET)
+    }
+
+    /// Reads the ladder of the chain on the point, `budget` is the maximum number of positions
+    /// read.
+    pub fn read_ladder_with_budget(&self, coord: Coord, budget: u32) -> Option<Ladder> {
+        match self.get_chain(coord)?.number_of_liberties() {
+            1 => Some(self.read_with(coord, budget, LadderReader::defender_loses)),
+            2 => Some(self.read_with(coord, budget, LadderReader::attacker_wins)),
+            _ => None,
+        }
+    }
+
+    /// Reads the ladder of the chain on the point from the first step `read`.
+    fn read_with(
+        &self,
+        coord: Coord,
+        budget: u32,
+        read: fn(&mut LadderReader, &Goban, Coord) -> bool,
+    ) -> Ladder {
+        let mut reader = LadderReader {
+            budget,
+            exhausted: false,
+            path: vec![],
+            breakers: vec![],
+        };
+        let captured = read(&mut reader, self, coord);
+        if captured {
+            reader.breakers.clear();
+        }
+        let mut breakers: Vec<Stone> = vec![];
+        for stone in reader.breakers {
+            if !breakers.contains(&stone) {
+                breakers.push(stone);
+            }
+        }
+        Ladder {
+            captured,
+            path: reader.path,
+            breakers,
+            budget_exhausted: reader.exhausted,
+        }
+    }
+
+    /// Returns true if the chain on the point is captured by a ladder.
+    /// See [`Goban::read_ladder`].
+    #[inline]
+    pub fn is_ladder_captured(&self, coord: Coord) -> bool {
+        self.read_ladder(coord)
+            .is_some_and(|ladder| ladder.captured)
+    }
+
+    /// Returns true if a stone of `color` on the point puts an opponent chain in atari that is
+    /// then captured by a ladder.
+    pub fn is_ladder_capture(&self, coord: Coord, color: Color) -> bool {
+        if self.get_color(coord).is_some() {
+            return false;
+        }
+        let mut goban = self.clone();
+        if !goban.play_stone(coord, color) {
+            return false;
+        }
+        let captured = goban
+            .get_connected_stones(coord)
+            .filter(|s| s.color != color)
+            .any(|s| {
+                goban.get_chain(s.coord).unwrap().is_atari() && goban.is_ladder_captured(s.coord)
+            });
+        captured
+    }
+
+    /// Reads the ladder after a stone of `color` is put on the point to save a chain in atari.
+    /// None if the move doesn't touch a chain in atari or is a suicide.
+    pub fn read_ladder_escape(&self, coord: Coord, color: Color) -> Option<Ladder> {
+        if self.get_color(coord).is_some() {
+            return None;
+        }
+        let in_atari = self
+            .get_connected_stones(coord)
+            .any(|s| s.color == color && self.get_chain(s.coord).unwrap().is_atari());
+        if !in_atari {
+            return None;
+        }
+        let mut goban = self.clone();
+        if !goban.play_stone(coord, color) {
+            return None;
+        }
+        // The attacker moves after the escape
+        let liberties = goban.chain_liberties(coord);
+        Some(match liberties[..] {
+            [liberty] => Ladder {
+                captured: true,
+                path: vec![Stone {
+                    coord: liberty,
+                    color: !color,
+                }],
+                ..Default::default()
+            },
+            [_, _] => goban.read_with(coord, LADDER_BUDGET, LadderReader::attacker_wins),
+            _ => Ladder::default(),
+        })
+    }
+
+    /// Returns true if a stone of `color` on the point saves one of his chains from a ladder.
+    #[inline]
+    pub fn is_ladder_escape(&self, coord: Coord, color: Color) -> bool {
+        self.read_ladder_escape(coord, color)
+            .is_some_and(|ladder| !ladder.captured)
+    }
+}
+
+impl LadderReader {
+    /// Consumes one position of the budget, false if the budget is exhausted.
+    fn spend(&mut self) -> bool {
+        if self.budget == 0 {
+            self.exhausted = true;
+            false
+        } else {
+            self.budget -= 1;
+            true
+        }
+    }
+
+    /// The chain on `prey` is in atari and the defender moves.
+    fn defender_loses(&mut self, goban: &Goban, prey: Coord) -> bool {
+        if !self.spend() {
+            return false;
+        }
+        let chain = goban.get_chain(prey).unwrap();
+        let color = chain.color;
+        // Extending first, it's the main line.
+        let mut candidates = goban.chain_liberties(prey);
+        // Capturing a chain around gives new liberties
+        for stone in goban.chain_stones(goban.get_chain_idx(prey).unwrap()) {
+            for attacker in goban.get_connected_stones(stone.coord) {
+                if attacker.color != color && goban.get_chain(attacker.coord).unwrap().is_atari() {
+                    for liberty in goban.chain_liberties(attacker.coord) {
+                        if !candidates.contains(&liberty) {
+                            candidates.push(liberty);
+                        }
+                    }
+                }
+            }
+        }
+
+        let start = self.path.len();
+        let mut main_line = None;
+        for candidate in candidates {
+            self.path.truncate(start);
+            let mut next = goban.clone();
+            if !next.play_stone(candidate, color) {
+                continue;
+            }
+            self.path.push(Stone {
+                coord: candidate,
+                color,
+            });
+            let liberties = next.get_chain(prey).unwrap().number_of_liberties();
+            if liberties >= 3 {
+                self.add_breakers(goban, &next, prey, candidate);
+                return false;
+            }
+            if liberties == 2 && !self.attacker_wins(&next, prey) {
+                return false;
+            }
+            if main_line.is_none() {
+                main_line = Some(self.path[start..].to_vec());
+            }
+        }
+        self.path.truncate(start);
+        self.path.extend(main_line.unwrap_or_default());
+        true
+    }
+
+    /// The chain on `prey` has two liberties and the attacker moves.
+    fn attacker_wins(&mut self, goban: &Goban, prey: Coord) -> bool {
+        if !self.spend() {
+            return false;
+        }
+        let attacker = !goban.get_chain(prey).unwrap().color;
+        let start = self.path.len();
+        let breakers_start = self.breakers.len();
+        let mut main_line = None;
+        for liberty in goban.chain_liberties(prey) {
+            self.path.truncate(start);
+            let mut next = goban.clone();
+            if !next.play_stone(liberty, attacker) {
+                continue;
+            }
+            self.path.push(Stone {
+                coord: liberty,
+                color: attacker,
+            });
+            if self.defender_loses(&next, prey) {
+                self.breakers.truncate(breakers_start);
+                return true;
+            }
+            if main_line.is_none() {
+                main_line = Some(self.path[start..].to_vec());
+            }
+        }
+        self.path.truncate(start);
+        self.path.extend(main_line.unwrap_or_default());
+        false
+    }
+
+    /// The defender escaped by playing on `played`, the stones he connected to and the stones
+    /// he captured are the breakers.
+    fn add_breakers(&mut self, before: &Goban, after: &Goban, prey: Coord, played: Coord) {
+        let prey_color = before.get_chain(prey).unwrap().color;
+        let prey_idx = before.get_chain_idx(prey).unwrap();
+        let after_idx = after.get_chain_idx(prey).unwrap();
+        for stone in after.chain_stones(after_idx) {
+            if stone.coord != played && before.get_chain_idx(stone.coord) != Some(prey_idx) {
+                self.breakers.push(stone);
+            }
+        }
+        for stone in before.get_stones() {
+            if stone.color != prey_color && after.get_color(stone.coord).is_none() {
+                self.breakers.push(stone);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pieces::goban::Goban;
+    use crate::pieces::stones::{Color, Stone};
+
+    #[test]
+    fn ladder() {
+        let mut goban = Goban::new((9, 9));
+        // White stone in atari, the ladder goes to the top right corner.
+        goban.push((4, 4), Color::White);
+        goban.push_many(&[(3, 4), (4, 3), (5, 4), (5, 5)], Color::Black);
+        let ladder = goban.read_ladder((4, 4)).unwrap();
+        assert!(ladder.captured);
+        assert!(ladder.breakers.is_empty());
+        assert_eq!(ladder.path[0].coord, (4, 5));
+        assert_eq!(ladder.path[0].color, Color::White);
+        assert!(ladder.path.iter().all(|s| s.coord.0 <= 4 && s.coord.1 >= 4));
+
+        let mut breaker = goban.clone();
+        breaker.push((7, 1), Color::White);
+        assert!(breaker.is_ladder_captured((4, 4)));
+        breaker.push((1, 7), Color::White);
+        assert!(!breaker.is_ladder_captured((4, 4)));
+
+        // The budget is too small to read the ladder
+        let ladder = goban.read_ladder_with_budget((4, 4), 5).unwrap();
+        assert!(!ladder.captured);
+        assert!(ladder.budget_exhausted);
+
+        goban.remove_stone((5, 4));
+        assert!(goban.is_ladder_captured((4, 4)));
+        assert!(goban.is_ladder_capture((5, 4), Color::Black));
+        assert!(goban.read_ladder((3, 4)).is_none());
+    }
+
+    #[test]
+    fn ladder_escape() {
+        let mut goban = Goban::new((9, 9));
+        goban.push((4, 4), Color::White);
+        goban.push_many(&[(3, 4), (4, 3), (5, 4), (5, 5)], Color::Black);
+        assert!(!goban.is_ladder_escape((4, 5), Color::White));
+        goban.push((2, 6), Color::White);
+        assert!(goban.is_ladder_escape((4, 5), Color::White));
+        // Not next to a chain in atari
+        assert!(goban.read_ladder_escape((0, 0), Color::White).is_none());
+
+        // The escape is a self atari, black captures at once
+        let mut goban = Goban::new((9, 9));
+        goban.push((4, 4), Color::White);
+        goban.push_many(&[(3, 4), (4, 3), (5, 4), (3, 5), (5, 5)], Color::Black);
+        let ladder = goban.read_ladder_escape((4, 5), Color::White).unwrap();
+        assert!(ladder.captured);
+        assert_eq!(
+            ladder.path,
+            vec![Stone {
+                coord: (4, 6),
+                color: Color::Black
+            }]
+        );
+        assert!(!goban.is_ladder_escape((4, 5), Color::White));
+    }
+}
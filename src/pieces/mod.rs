@@ -9,9 +9,18 @@ pub(super) type BoardIdx = usize;
 
 pub(super) type Connections<T=BoardIdx> = ArrayVec<T, 4>;
 
+#[cfg(feature = "bitboard")]
+pub mod bitboard;
+pub mod chain;
+pub mod eyes;
 pub mod group;
 pub mod goban;
+pub mod graph;
+pub mod ladder;
+pub mod region;
+pub mod shapes;
 pub mod stones;
+pub mod tactics;
 pub mod territory;
 pub mod util;
 pub mod zobrist;
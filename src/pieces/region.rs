@@ -0,0 +1,160 @@
+//! Module with the maximal empty regions of the goban and the chains around them.
+//!
+//! # Example
+//! ```
+//! use goban::pieces::goban::Goban;
+//! use goban::pieces::stones::Color;
+//!
+//! let mut goban = Goban::new((5, 5));
+//! goban.push_many(&[(0, 1), (1, 0)], Color::Black);
+//! goban.push((4, 4), Color::White);
+//! let regions = goban.empty_regions();
+//! assert_eq!(regions.len(), 2);
+//! assert_eq!(regions[0].points, vec![(0, 0)]);
+//! assert_eq!(regions[0].owner(), Some(Color::Black));
+//! assert!(regions[0].is_small_eye());
+//! assert_eq!(regions[1].owner(), None);
+//! ```
+
+use crate::pieces::chain::ChainId;
+use crate::pieces::goban::Goban;
+use crate::pieces::group::{get, iter_ones, Liberties, EMPTY_LIBERTIES};
+use crate::pieces::stones::{Color, MaybeColor, EMPTY};
+use crate::pieces::util::coord::{one_to_2dim, Coord};
+
+/// Biggest empty region counted as an eye space, bigger regions are territories.
+pub const MAX_EYE_SIZE: usize = 7;
+
+/// A maximal set of connected empty points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmptyRegion {
+    /// Points of the region in row major order.
+    pub points: Vec<Coord>,
+    /// Chains with a stone next to the region.
+    pub chains: Vec<ChainId>,
+    /// True if a black stone is next to the region.
+    pub borders_black: bool,
+    /// True if a white stone is next to the region.
+    pub borders_white: bool,
+}
+
+impl EmptyRegion {
+    /// Number of points of the region.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Color of the stones around the region, None if both colors or no stones are next to it.
+    #[inline]
+    pub fn owner(&self) -> MaybeColor {
+        match (self.borders_black, self.borders_white) {
+            (true, false) => Some(Color::Black),
+            (false, true) => Some(Color::White),
+            _ => None,
+        }
+    }
+
+    /// The region is surrounded by one color and has at most [`MAX_EYE_SIZE`] points.
+    #[inline]
+    pub fn is_small_eye(&self) -> bool {
+        self.owner().is_some() && self.size() <= MAX_EYE_SIZE
+    }
+}
+
+/// An empty region as a bitset, the points aren't listed so it doesn't allocate.
+pub(crate) struct RegionMask {
+    pub points: Liberties,
+    pub borders_black: bool,
+    pub borders_white: bool,
+}
+
+impl Goban {
+    /// Returns every maximal empty region of the goban, in the row major order of their first
+    /// point.
+    pub fn empty_regions(&self) -> Vec<EmptyRegion> {
+        let size = self.size();
+        self.empty_region_masks()
+            .map(|mask| {
+                let mut chains = vec![];
+                for point in iter_ones(&mask.points) {
+                    for neighbor in self.neighbors_idx(point) {
+                        if let Some(chain) = self.get_chain(neighbor) {
+                            if !chains.contains(&chain.id) {
+                                chains.push(chain.id);
+                            }
+                        }
+                    }
+                }
+                EmptyRegion {
+                    points: iter_ones(&mask.points)
+                        .map(|point| one_to_2dim(size, point))
+                        .collect(),
+                    chains,
+                    borders_black: mask.borders_black,
+                    borders_white: mask.borders_white,
+                }
+            })
+            .collect()
+    }
+
+    /// Maximal empty regions as bitsets with the colors next to them, in the order of
+    /// [`Goban::empty_regions`]. The territories are counted with them.
+    pub(crate) fn empty_region_masks(&self) -> impl Iterator<Item = RegionMask> + '_ {
+        let board_length = self.size().0 as usize * self.size().1 as usize;
+        let mut explored = EMPTY_LIBERTIES;
+        let mut next = 0;
+        std::iter::from_fn(move || {
+            let start = (next..board_length)
+                .find(|&idx| self.board()[idx].is_none() && !get(idx, &explored))?;
+            next = start + 1;
+            let points = self.fill_region(start, EMPTY, &mut explored);
+            let (mut borders_black, mut borders_white) = (false, false);
+            for point in iter_ones(&points) {
+                for neighbor in self.neighbors_idx(point) {
+                    match self.get_color(neighbor) {
+                        Some(Color::Black) => borders_black = true,
+                        Some(Color::White) => borders_white = true,
+                        None => {}
+                    }
+                }
+            }
+            Some(RegionMask {
+                points,
+                borders_black,
+                borders_white,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pieces::goban::Goban;
+    use crate::pieces::stones::Color;
+
+    #[test]
+    fn empty_regions() {
+        let mut goban = Goban::new((9, 9));
+        assert_eq!(goban.empty_regions().len(), 1);
+        assert_eq!(goban.empty_regions()[0].owner(), None);
+        assert!(!goban.empty_regions()[0].is_small_eye());
+
+        // A black wall and a white stone on the right side
+        goban.push_many(
+            &[(0, 3), (1, 3), (2, 3), (3, 2), (3, 1), (3, 0)],
+            Color::Black,
+        );
+        goban.push((8, 8), Color::White);
+        let regions = goban.empty_regions();
+        assert_eq!(regions.len(), 2);
+        let corner = &regions[0];
+        assert_eq!(corner.size(), 9);
+        assert_eq!(corner.owner(), Some(Color::Black));
+        assert_eq!(corner.chains.len(), 2);
+        assert!(!corner.is_small_eye());
+        let outside = &regions[1];
+        assert!(outside.borders_black && outside.borders_white);
+        assert_eq!(outside.chains.len(), 3);
+    }
+}
@@ -0,0 +1,257 @@
+//! Module for the recognition of the shapes of eye spaces.
+//!
+//! The shape of an eye space is found whatever its rotation or reflection. The status assumes
+//! the eye space is empty and in the center of the goban, some shapes are worse in the corner.
+//!
+//! # Example
+//! ```
+//! use goban::pieces::goban::Goban;
+//! use goban::pieces::shapes::{EyeShape, ShapeStatus};
+//! use goban::pieces::stones::Color;
+//!
+//! let mut goban = Goban::new((9, 9));
+//! goban.push_many(&[(0, 3), (1, 0), (1, 1), (1, 2), (1, 3)], Color::Black);
+//! goban.push((8, 8), Color::White);
+//! let nakade = goban.nakade((0, 0), Color::Black).unwrap();
+//! assert_eq!(nakade.shape, EyeShape::StraightThree);
+//! assert_eq!(nakade.status, ShapeStatus::Unsettled);
+//! assert_eq!(nakade.vital_points, vec![(0, 1)]);
+//! ```
+
+use crate::pieces::eyes::Eye;
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::Color;
+use crate::pieces::util::coord::{neighbor_coords, Coord};
+
+/// Shape of an eye space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EyeShape {
+    One,
+    Two,
+    StraightThree,
+    BentThree,
+    SquareFour,
+    StraightFour,
+    BentFour,
+    PyramidFour,
+    ZigzagFour,
+    BulkyFive,
+    CrossedFive,
+    RabbitySix,
+    /// Any other shape, with its number of points.
+    Other(usize),
+}
+
+/// Status of the group around an eye space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShapeStatus {
+    /// Two eyes even if the opponent plays first.
+    Alive,
+    /// Only one eye even if the owner plays first.
+    Dead,
+    /// Two eyes for the owner if he plays first on the vital point, one eye otherwise.
+    Unsettled,
+}
+
+/// Shape, status and vital points of an eye space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nakade {
+    pub shape: EyeShape,
+    pub status: ShapeStatus,
+    /// Points that decide the status of an unsettled shape, empty otherwise.
+    pub vital_points: Vec<Coord>,
+}
+
+const SHAPES: [(EyeShape, &[Coord]); 12] = [
+    (EyeShape::One, &[(0, 0)]),
+    (EyeShape::Two, &[(0, 0), (0, 1)]),
+    (EyeShape::StraightThree, &[(0, 0), (0, 1), (0, 2)]),
+    (EyeShape::BentThree, &[(0, 0), (0, 1), (1, 0)]),
+    (EyeShape::SquareFour, &[(0, 0), (0, 1), (1, 0), (1, 1)]),
+    (EyeShape::StraightFour, &[(0, 0), (0, 1), (0, 2), (0, 3)]),
+    (EyeShape::BentFour, &[(0, 0), (1, 0), (2, 0), (2, 1)]),
+    (EyeShape::PyramidFour, &[(0, 0), (0, 1), (0, 2), (1, 1)]),
+    (EyeShape::ZigzagFour, &[(0, 0), (0, 1), (1, 1), (1, 2)]),
+    (
+        EyeShape::BulkyFive,
+        &[(0, 0), (0, 1), (0, 2), (1, 0), (1, 1)],
+    ),
+    (
+        EyeShape::CrossedFive,
+        &[(0, 1), (1, 0), (1, 1), (1, 2), (2, 1)],
+    ),
+    (
+        EyeShape::RabbitySix,
+        &[(0, 1), (1, 0), (1, 1), (1, 2), (2, 1), (2, 2)],
+    ),
+];
+
+/// Points moved in the top left corner and sorted, the smallest of the 8 symmetries.
+/// Two regions with the same shape have the same result.
+pub fn canonical_shape(points: &[Coord]) -> Vec<Coord> {
+    let mut best: Option<Vec<Coord>> = None;
+    for symmetry in 0..8 {
+        let transformed: Vec<(i16, i16)> = points
+            .iter()
+            .map(|&(line, column)| {
+                let (mut l, mut c) = (line as i16, column as i16);
+                if symmetry & 1 != 0 {
+                    l = -l;
+                }
+                if symmetry & 2 != 0 {
+                    c = -c;
+                }
+                if symmetry & 4 != 0 {
+                    std::mem::swap(&mut l, &mut c);
+                }
+                (l, c)
+            })
+            .collect();
+        let min_line = transformed.iter().map(|p| p.0).min().unwrap_or(0);
+        let min_column = transformed.iter().map(|p| p.1).min().unwrap_or(0);
+        let mut shape: Vec<Coord> = transformed
+            .into_iter()
+            .map(|(l, c)| ((l - min_line) as u8, (c - min_column) as u8))
+            .collect();
+        shape.sort_unstable();
+        if best.as_ref().is_none_or(|best| shape < *best) {
+            best = Some(shape);
+        }
+    }
+    best.unwrap_or_default()
+}
+
+impl EyeShape {
+    /// Finds the shape of the points, they must be connected.
+    pub fn from_points(points: &[Coord]) -> Self {
+        let shape = canonical_shape(points);
+        SHAPES
+            .iter()
+            .find(|(_, template)| {
+                template.len() == shape.len() && canonical_shape(template) == shape
+            })
+            .map_or(EyeShape::Other(points.len()), |&(eye_shape, _)| eye_shape)
+    }
+
+    /// Status of a group with this eye space and no other eye.
+    pub const fn status(self) -> ShapeStatus {
+        match self {
+            EyeShape::One | EyeShape::Two | EyeShape::SquareFour => ShapeStatus::Dead,
+            EyeShape::StraightThree
+            | EyeShape::BentThree
+            | EyeShape::PyramidFour
+            | EyeShape::BulkyFive
+            | EyeShape::CrossedFive
+            | EyeShape::RabbitySix => ShapeStatus::Unsettled,
+            EyeShape::StraightFour
+            | EyeShape::BentFour
+            | EyeShape::ZigzagFour
+            | EyeShape::Other(_) => ShapeStatus::Alive,
+        }
+    }
+}
+
+impl Nakade {
+    /// Classifies the points of an eye space. The vital points of an unsettled shape are the
+    /// points with the most neighbors in the eye space.
+    pub fn from_points(points: &[Coord]) -> Self {
+        let shape = EyeShape::from_points(points);
+        let status = shape.status();
+        let vital_points = if status == ShapeStatus::Unsettled {
+            let neighbors = |coord: Coord| {
+                neighbor_coords(coord)
+                    .iter()
+                    .filter(|n| points.contains(n))
+                    .count()
+            };
+            let max = points.iter().map(|&p| neighbors(p)).max().unwrap_or(0);
+            let mut vital: Vec<Coord> = points
+                .iter()
+                .copied()
+                .filter(|&p| neighbors(p) == max)
+                .collect();
+            vital.sort_unstable();
+            vital
+        } else {
+            vec![]
+        };
+        Nakade {
+            shape,
+            status,
+            vital_points,
+        }
+    }
+}
+
+impl Eye {
+    /// Shape, status and vital points of the eye.
+    #[inline]
+    pub fn nakade(&self) -> Nakade {
+        Nakade::from_points(&self.points)
+    }
+}
+
+impl Goban {
+    /// Classifies the eye space of `color` with the point, None if the point isn't in an empty
+    /// region surrounded by `color`.
+    pub fn nakade(&self, coord: Coord, color: Color) -> Option<Nakade> {
+        self.eyes(color)
+            .into_iter()
+            .find(|eye| eye.points.contains(&coord))
+            .map(|eye| eye.nakade())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes() {
+        assert_eq!(
+            EyeShape::from_points(&[(3, 3), (4, 3), (5, 3)]),
+            EyeShape::StraightThree
+        );
+        assert_eq!(
+            EyeShape::from_points(&[(3, 3), (4, 3), (4, 2)]),
+            EyeShape::BentThree
+        );
+        // Rotated and reflected bulky five
+        let bulky = [(2, 2), (3, 2), (4, 2), (3, 3), (4, 3)];
+        assert_eq!(EyeShape::from_points(&bulky), EyeShape::BulkyFive);
+        let nakade = Nakade::from_points(&bulky);
+        assert_eq!(nakade.status, ShapeStatus::Unsettled);
+        assert_eq!(nakade.vital_points, vec![(3, 2)]);
+
+        let rabbity = [(5, 5), (5, 6), (6, 4), (6, 5), (6, 6), (7, 5)];
+        let nakade = Nakade::from_points(&rabbity);
+        assert_eq!(nakade.shape, EyeShape::RabbitySix);
+        assert_eq!(nakade.vital_points, vec![(6, 5)]);
+
+        let nakade = Nakade::from_points(&[(0, 0), (0, 1), (1, 1), (1, 0)]);
+        assert_eq!(nakade.shape, EyeShape::SquareFour);
+        assert_eq!(nakade.status, ShapeStatus::Dead);
+        assert!(nakade.vital_points.is_empty());
+
+        let straight_five = [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)];
+        assert_eq!(EyeShape::from_points(&straight_five), EyeShape::Other(5));
+        assert_eq!(EyeShape::Other(5).status(), ShapeStatus::Alive);
+    }
+
+    #[test]
+    fn nakade_on_goban() {
+        let mut goban = Goban::new((9, 9));
+        // Black group with a pyramid four in the corner.
+        goban.push_many(
+            &[(0, 3), (1, 0), (1, 2), (1, 3), (2, 0), (2, 1), (2, 2)],
+            Color::Black,
+        );
+        goban.push((8, 8), Color::White);
+        let nakade = goban.nakade((0, 0), Color::Black).unwrap();
+        assert_eq!(nakade.shape, EyeShape::PyramidFour);
+        assert_eq!(nakade.vital_points, vec![(0, 1)]);
+        assert_eq!(goban.count_eyes((1, 2)), 1);
+        assert!(goban.nakade((0, 0), Color::White).is_none());
+        assert!(goban.nakade((5, 5), Color::Black).is_none());
+    }
+}
@@ -0,0 +1,241 @@
+//! Module for the tactical reading of chains with few liberties.
+//!
+//! The reader answers two questions on a chain with one to three liberties:
+//! can it be captured if the attacker moves first, and can it be saved if the defender
+//! moves first. The attacker plays on the liberties of the chain, the defender extends or
+//! captures the stones around. A chain of the defender with three liberties after his move is
+//! considered safe.
+//! The reading is bounded by a number of positions and a depth, when one of them is exhausted
+//! the chain is considered saved.
+//!
+//! # Example
+//! ```
+//! use goban::pieces::goban::Goban;
+//! use goban::pieces::stones::Color;
+//!
+//! let mut goban = Goban::new((9, 9));
+//! goban.push((0, 0), Color::White);
+//! goban.push((0, 1), Color::Black);
+//! let capture = goban.can_capture((0, 0)).unwrap();
+//! assert!(capture.success);
+//! assert_eq!(capture.key_moves, vec![(1, 0)]);
+//! ```
+
+use crate::pieces::goban::Goban;
+use crate::pieces::util::coord::Coord;
+
+/// Default number of positions read by the tactical reader.
+pub const TACTICS_BUDGET: u32 = 5000;
+
+/// Maximum number of moves in a line read by the tactical reader.
+pub const MAX_TACTICS_DEPTH: u32 = 60;
+
+/// Result of a tactical reading.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Tactic {
+    /// True if the side that moves first reaches its goal: capturing the chain for the
+    /// attacker, saving it for the defender.
+    pub success: bool,
+    /// Moves that reach the goal, in the order they were tried.
+    pub key_moves: Vec<Coord>,
+    /// True if the reading was cut by the budget or the depth.
+    pub budget_exhausted: bool,
+}
+
+struct TacticsReader {
+    budget: u32,
+    exhausted: bool,
+}
+
+impl Goban {
+    /// Can the chain on the point be captured if the attacker moves first.
+    /// None if the point is empty or the chain has more than three liberties.
+    #[inline]
+    pub fn can_capture(&self, coord: Coord) -> Option<Tactic> {
+        self.can_capture_with_budget(coord, TACTICS_BUDGET)
+    }
+
+    /// Same as [`Goban::can_capture`] with a budget of `budget` positions.
+    pub fn can_capture_with_budget(&self, coord: Coord, budget: u32) -> Option<Tactic> {
+        let chain = self.get_chain(coord)?;
+        if chain.number_of_liberties() > 3 {
+            return None;
+        }
+        let attacker = !chain.color;
+        let mut reader = TacticsReader {
+            budget,
+            exhausted: false,
+        };
+        let mut key_moves = vec![];
+        for play in attack_moves(self, coord) {
+            let mut next = self.clone();
+            if !next.play_stone(play, attacker) {
+                continue;
+            }
+            if !reader.defend(&next, coord, 1) {
+                key_moves.push(play);
+            }
+        }
+        Some(Tactic {
+            success: !key_moves.is_empty(),
+            key_moves,
+            budget_exhausted: reader.exhausted,
+        })
+    }
+
+    /// Can the chain on the point be saved if the defender moves first.
+    /// None if the point is empty or the chain has more than three liberties.
+    #[inline]
+    pub fn can_save(&self, coord: Coord) -> Option<Tactic> {
+        self.can_save_with_budget(coord, TACTICS_BUDGET)
+    }
+
+    /// Same as [`Goban::can_save`] with a budget of `budget` positions.
+    pub fn can_save_with_budget(&self, coord: Coord, budget: u32) -> Option<Tactic> {
+        let chain = self.get_chain(coord)?;
+        if chain.number_of_liberties() > 3 {
+            return None;
+        }
+        let defender = chain.color;
+        let mut reader = TacticsReader {
+            budget,
+            exhausted: false,
+        };
+        let mut key_moves = vec![];
+        for play in defense_moves(self, coord) {
+            let mut next = self.clone();
+            if !next.play_stone(play, defender) {
+                continue;
+            }
+            if !reader.attack(&next, coord, 1) {
+                key_moves.push(play);
+            }
+        }
+        // The chain doesn't need to move if it can't be captured.
+        let safe = key_moves.is_empty() && !reader.attack(self, coord, 0);
+        Some(Tactic {
+            success: safe || !key_moves.is_empty(),
+            key_moves,
+            budget_exhausted: reader.exhausted,
+        })
+    }
+}
+
+/// The attacker plays on the liberties of the chain.
+fn attack_moves(goban: &Goban, prey: Coord) -> Vec<Coord> {
+    goban.chain_liberties(prey)
+}
+
+/// The defender extends on his liberties or captures a chain in atari around.
+fn defense_moves(goban: &Goban, prey: Coord) -> Vec<Coord> {
+    let color = goban.get_chain(prey).unwrap().color;
+    let mut moves = goban.chain_liberties(prey);
+    for stone in goban.chain_stones(goban.get_chain_idx(prey).unwrap()) {
+        for other in goban.get_connected_stones(stone.coord) {
+            if other.color != color && goban.get_chain(other.coord).unwrap().is_atari() {
+                for liberty in goban.chain_liberties(other.coord) {
+                    if !moves.contains(&liberty) {
+                        moves.push(liberty);
+                    }
+                }
+            }
+        }
+    }
+    moves
+}
+
+impl TacticsReader {
+    /// Consumes one position of the budget, false if the budget or the depth are exhausted.
+    fn spend(&mut self, depth: u32) -> bool {
+        if self.budget == 0 || depth >= MAX_TACTICS_DEPTH {
+            self.exhausted = true;
+            false
+        } else {
+            self.budget -= 1;
+            true
+        }
+    }
+
+    /// The attacker moves, true if the chain on `prey` is captured.
+    fn attack(&mut self, goban: &Goban, prey: Coord, depth: u32) -> bool {
+        let Some(chain) = goban.get_chain(prey) else {
+            return true;
+        };
+        let liberties = chain.number_of_liberties();
+        if liberties == 1 {
+            return true;
+        }
+        if liberties > 3 || !self.spend(depth) {
+            return false;
+        }
+        let attacker = !chain.color;
+        attack_moves(goban, prey).into_iter().any(|play| {
+            let mut next = goban.clone();
+            next.play_stone(play, attacker) && !self.defend(&next, prey, depth + 1)
+        })
+    }
+
+    /// The defender moves, true if the chain on `prey` is saved.
+    fn defend(&mut self, goban: &Goban, prey: Coord, depth: u32) -> bool {
+        let Some(chain) = goban.get_chain(prey) else {
+            return false;
+        };
+        if chain.number_of_liberties() >= 3 || !self.spend(depth) {
+            return true;
+        }
+        let defender = chain.color;
+        defense_moves(goban, prey).into_iter().any(|play| {
+            let mut next = goban.clone();
+            next.play_stone(play, defender) && !self.attack(&next, prey, depth + 1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pieces::goban::Goban;
+    use crate::pieces::stones::Color;
+
+    #[test]
+    fn capture_and_save() {
+        let mut goban = Goban::new((9, 9));
+        // Stone in the corner, the ladder on the first line works.
+        goban.push((0, 0), Color::White);
+        goban.push((0, 1), Color::Black);
+        let capture = goban.can_capture((0, 0)).unwrap();
+        assert!(capture.success);
+        assert_eq!(capture.key_moves, vec![(1, 0)]);
+        let save = goban.can_save((0, 0)).unwrap();
+        assert!(!save.success);
+        assert!(!save.budget_exhausted);
+
+        // A white stone on the first line helps.
+        goban.push((4, 0), Color::White);
+        let save = goban.can_save((0, 0)).unwrap();
+        assert!(save.success);
+        assert_eq!(save.key_moves, vec![(1, 0)]);
+
+        // Three liberties on the edge can't be captured.
+        goban.push((0, 6), Color::White);
+        let capture = goban.can_capture((0, 6)).unwrap();
+        assert!(!capture.success);
+        assert!(goban.can_save((0, 6)).unwrap().success);
+        assert!(goban.can_capture((4, 4)).is_none());
+    }
+
+    #[test]
+    fn capture_around() {
+        let mut goban = Goban::new((9, 9));
+        // The white stone in atari is saved by extending or by capturing one of the black
+        // stones around.
+        goban.push_many(&[(1, 1), (0, 2), (2, 2)], Color::White);
+        goban.push_many(&[(0, 1), (1, 0), (1, 2)], Color::Black);
+        assert!(goban.get_chain((1, 1)).unwrap().is_atari());
+        let save = goban.can_save((1, 1)).unwrap();
+        assert!(save.success);
+        assert_eq!(save.key_moves, vec![(2, 1), (0, 0), (1, 3)]);
+
+        let exhausted = goban.can_save_with_budget((1, 1), 0).unwrap();
+        assert!(exhausted.budget_exhausted);
+    }
+}
@@ -1,10 +1,15 @@
 //! Module with tools for getting the connected stones and liberties.
+//!
+//! The searches mark the points in bitsets of the size of the goban, like the liberties of
+//! the chains, so they don't allocate sets of points.
 
 use std::collections::HashSet;
 
 use crate::pieces::goban::Goban;
-use crate::pieces::stones::Point;
-use crate::pieces::stones::{Color, EMPTY};
+use crate::pieces::group::{count_ones, get, iter_ones, merge, set, Liberties, EMPTY_LIBERTIES};
+use crate::pieces::stones::{MaybeColor, Point, EMPTY};
+use crate::pieces::util::coord::{one_to_2dim, two_to_1dim};
+use crate::pieces::BoardIdx;
 
 impl Goban {
     ///
@@ -16,24 +21,13 @@ impl Goban {
     /// It will return the stone alone if it's lonely
     ///
     pub fn get_group_from_point(&self, stone: Point) -> HashSet<Point> {
-        let mut explored = HashSet::<Point>::new();
-        explored.insert(stone);
-
-        let mut to_explore: Vec<Point> = self
-            .get_connected_points(stone.coord)
-            .filter(|p| p.color == stone.color)
-            .collect(); // Acquiring all the neighbors
-
-        while let Some(stone_to_explore) = to_explore.pop() {
-            // exploring the graph
-            explored.insert(stone_to_explore);
-
-            to_explore.extend(
-                self.get_connected_points(stone_to_explore.coord)
-                    .filter(|p| p.color == stone.color && !explored.contains(p)),
-            )
-        }
-        explored
+        let mut explored = EMPTY_LIBERTIES;
+        let start = two_to_1dim(self.size(), stone.coord);
+        self.points_of_set(
+            &self.fill_region(start, stone.color, &mut explored),
+            stone.color,
+        )
+        .collect()
     }
 
     ///
@@ -46,12 +40,13 @@ impl Goban {
         &self,
         stones: impl Iterator<Item = Point>,
     ) -> Vec<HashSet<Point>> {
+        let mut handled = EMPTY_LIBERTIES;
         let mut groups_of_stones: Vec<HashSet<Point>> = Default::default();
         for s in stones {
-            let is_handled = groups_of_stones.iter().any(|set| set.contains(&s));
-
-            if !is_handled {
-                groups_of_stones.push(self.get_group_from_point(s))
+            let start = two_to_1dim(self.size(), s.coord);
+            if !get(start, &handled) {
+                let group = self.fill_region(start, s.color, &mut handled);
+                groups_of_stones.push(self.points_of_set(&group, s.color).collect());
             }
         }
         groups_of_stones
@@ -59,36 +54,15 @@ impl Goban {
 
     /// Get two iterators of empty points. The first one is the territory of black the second is white territory
     pub fn get_territories(&self) -> (impl Iterator<Item = Point>, impl Iterator<Item = Point>) {
-        let empty_chains =
-            self.get_chains_from_stones(self.get_empty_coords().map(|coord| Point {
-                coord,
-                color: EMPTY,
-            }));
-        let mut white_territory = Vec::with_capacity(50);
-        let mut black_territory = Vec::with_capacity(50);
-        'outer: for empty_group in empty_chains {
-            let mut neutral = (false, false);
-            for empty_intersection in &empty_group {
-                for point in self.get_connected_points(empty_intersection.coord) {
-                    if point.color == Some(Color::White) {
-                        neutral.1 = true; // found white stone
-                    }
-                    if point.color == Some(Color::Black) {
-                        neutral.0 = true; // found black stone
-                    }
-                    if neutral.0 && neutral.1 {
-                        // if the territory is surrounded by two colors then pass
-                        continue 'outer;
-                    }
-                }
-            }
-            if neutral.0 && !neutral.1 {
-                black_territory.extend(empty_group.into_iter())
-            } else if !neutral.0 && neutral.1 {
-                white_territory.extend(empty_group.into_iter())
-            }
-        }
-        (black_territory.into_iter(), white_territory.into_iter())
+        let (black_territory, white_territory) = self.territory_sets();
+        (
+            self.points_of_set(&black_territory, EMPTY)
+                .collect::<Vec<_>>()
+                .into_iter(),
+            self.points_of_set(&white_territory, EMPTY)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        )
     }
 
     ///
@@ -99,7 +73,97 @@ impl Goban {
     ///
     #[inline]
     pub fn calculate_territories(&self) -> (usize, usize) {
-        let (black_territory, white_territory) = self.get_territories();
-        (black_territory.count(), white_territory.count())
+        let (black_territory, white_territory) = self.territory_sets();
+        (count_ones(&black_territory), count_ones(&white_territory))
+    }
+
+    /// Returns the set of the points connected to the point with the color `color`, the point
+    /// included. The points found are marked in `explored` too.
+    pub(crate) fn fill_region(
+        &self,
+        start: BoardIdx,
+        color: MaybeColor,
+        explored: &mut Liberties,
+    ) -> Liberties {
+        let mut region = EMPTY_LIBERTIES;
+        set::<true>(start, &mut region);
+        set::<true>(start, explored);
+        let mut to_explore = vec![start];
+        while let Some(idx) = to_explore.pop() {
+            for neighbor in self.neighbors_idx(idx) {
+                if !get(neighbor, &region) && self.get_color(neighbor) == color {
+                    set::<true>(neighbor, &mut region);
+                    set::<true>(neighbor, explored);
+                    to_explore.push(neighbor);
+                }
+            }
+        }
+        region
+    }
+
+    /// Returns the (black, white) sets of the empty points surrounded by only one color.
+    fn territory_sets(&self) -> (Liberties, Liberties) {
+        let mut black_territory = EMPTY_LIBERTIES;
+        let mut white_territory = EMPTY_LIBERTIES;
+        for region in self.empty_region_masks() {
+            match (region.borders_black, region.borders_white) {
+                (true, false) => merge(&mut black_territory, &region.points),
+                (false, true) => merge(&mut white_territory, &region.points),
+                _ => {}
+            }
+        }
+        (black_territory, white_territory)
+    }
+
+    /// Points of the set with the color `color`, in row major order.
+    fn points_of_set<'a>(
+        &'a self,
+        points: &'a Liberties,
+        color: MaybeColor,
+    ) -> impl Iterator<Item = Point> + 'a {
+        iter_ones(points).map(move |idx| Point {
+            coord: one_to_2dim(self.size(), idx),
+            color,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pieces::goban::Goban;
+    use crate::pieces::stones::{Color, Point, EMPTY};
+
+    #[test]
+    fn flood_fill() {
+        let mut goban = Goban::new((9, 9));
+        goban.push_many(&[(0, 1), (1, 1), (1, 0), (4, 4)], Color::Black);
+        goban.push_many(&[(8, 7), (7, 8), (7, 7)], Color::White);
+        let stone = Point {
+            coord: (0, 1),
+            color: Some(Color::Black),
+        };
+        let group = goban.get_group_from_point(stone);
+        assert_eq!(group.len(), 3);
+        assert!(group.contains(&stone));
+        let corner = Point {
+            coord: (0, 0),
+            color: EMPTY,
+        };
+        assert_eq!(
+            goban
+                .get_group_from_point(corner)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![corner]
+        );
+
+        let chains = goban.get_chains_from_stones(goban.get_stones_by_color(Some(Color::Black)));
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0].len() + chains[1].len(), 4);
+
+        let (black, white) = goban.get_territories();
+        assert_eq!(black.map(|p| p.coord).collect::<Vec<_>>(), vec![(0, 0)]);
+        assert_eq!(white.map(|p| p.coord).collect::<Vec<_>>(), vec![(8, 8)]);
+        assert_eq!(goban.calculate_territories(), (1, 1));
     }
 }
@@ -1,4 +1,11 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{BitXor, BitXorAssign};
+use std::sync::Arc;
+
+use crate::pieces::goban::Goban;
 use crate::pieces::stones::Color;
+use crate::pieces::util::coord::{Coord, Size};
 
 const BOARD_SIZE: usize = 19 * 19;
 
@@ -733,10 +740,197 @@ pub const fn index_zobrist(index: usize, color: Color) -> u64 {
     ZOBRIST[index + (BOARD_SIZE * page_idx)]
 }
 
+/// Seed of the keys made by [`ZobristKeys::new`].
+pub const DEFAULT_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Generator of the keys, splitmix64 so the keys only depend on the seed.
+#[derive(Debug, Clone)]
+pub struct KeyGenerator(u64);
+
+impl KeyGenerator {
+    #[inline]
+    pub const fn new(seed: u64) -> Self {
+        KeyGenerator(seed)
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Width of a zobrist hash, 64 or 128 bits.
+pub trait ZobristHash:
+    Copy + Eq + Hash + Default + Debug + BitXor<Output = Self> + BitXorAssign
+{
+    fn generate(generator: &mut KeyGenerator) -> Self;
+}
+
+impl ZobristHash for u64 {
+    #[inline]
+    fn generate(generator: &mut KeyGenerator) -> Self {
+        generator.next_u64()
+    }
+}
+
+impl ZobristHash for u128 {
+    #[inline]
+    fn generate(generator: &mut KeyGenerator) -> Self {
+        (generator.next_u64() as u128) << 64 | generator.next_u64() as u128
+    }
+}
+
+/// Zobrist keys of a goban of any size: a key by point and color, a key by ko point and a key
+/// for white to move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZobristKeys<H = u64> {
+    size: Size,
+    stones: Vec<H>,
+    ko: Vec<H>,
+    white_to_move: H,
+}
+
+impl<H: ZobristHash> ZobristKeys<H> {
+    /// Keys of the size generated from [`DEFAULT_SEED`].
+    #[inline]
+    pub fn new(size: Size) -> Self {
+        Self::with_seed(size, DEFAULT_SEED)
+    }
+
+    /// The same seed and size always give the same keys.
+    pub fn with_seed(size: Size, seed: u64) -> Self {
+        let points = size.0 as usize * size.1 as usize;
+        let mut generator = KeyGenerator::new(seed);
+        let stones = (0..2 * points)
+            .map(|_| H::generate(&mut generator))
+            .collect();
+        let ko = (0..points).map(|_| H::generate(&mut generator)).collect();
+        ZobristKeys {
+            size,
+            stones,
+            ko,
+            white_to_move: H::generate(&mut generator),
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline]
+    fn point_idx(&self, (line, column): Coord) -> usize {
+        assert!(
+            line < self.size.0 && column < self.size.1,
+            "Coordinate {:?} outside a goban of size {:?}",
+            (line, column),
+            self.size
+        );
+        line as usize * self.size.1 as usize + column as usize
+    }
+
+    /// # Panics
+    /// If the point is outside the goban.
+    #[inline]
+    pub fn stone(&self, coord: Coord, color: Color) -> H {
+        self.stone_idx(self.point_idx(coord), color)
+    }
+
+    /// Key of the stone on the index of the board of a goban of the same size.
+    #[inline]
+    pub(crate) fn stone_idx(&self, idx: usize, color: Color) -> H {
+        self.stones[idx + self.ko.len() * (color as usize - 1)]
+    }
+
+    /// # Panics
+    /// If the point is outside the goban.
+    #[inline]
+    pub fn ko(&self, coord: Coord) -> H {
+        self.ko[self.point_idx(coord)]
+    }
+
+    #[inline]
+    pub fn white_to_move(&self) -> H {
+        self.white_to_move
+    }
+
+    /// Hash of the stones of the goban.
+    ///
+    /// # Panics
+    /// If the goban doesn't have the size of the keys.
+    pub fn hash_goban(&self, goban: &Goban) -> H {
+        assert_eq!(goban.size(), self.size, "The keys are for another size");
+        goban.get_stones().fold(H::default(), |hash, stone| {
+            hash ^ self.stone(stone.coord, stone.color)
+        })
+    }
+
+    /// Hash of the stones, the player to move and the ko point.
+    ///
+    /// # Panics
+    /// If the goban doesn't have the size of the keys.
+    pub fn hash_position(&self, goban: &Goban, turn: Color, ko_point: Option<Coord>) -> H {
+        self.with_turn_and_ko(self.hash_goban(goban), turn, ko_point)
+    }
+
+    /// Adds the player to move and the ko point to the hash of the stones.
+    #[inline]
+    pub(crate) fn with_turn_and_ko(&self, mut hash: H, turn: Color, ko_point: Option<Coord>) -> H {
+        if turn == Color::White {
+            hash ^= self.white_to_move;
+        }
+        if let Some(ko_point) = ko_point {
+            hash ^= self.ko(ko_point);
+        }
+        hash
+    }
+}
+
+/// Hash of the stones of a goban kept up to date with keys of any width, the keys are shared
+/// by the copies.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct RunningHash<H> {
+    keys: Arc<ZobristKeys<H>>,
+    hash: H,
+}
+
+impl<H: ZobristHash> RunningHash<H> {
+    pub fn new(keys: Arc<ZobristKeys<H>>, goban: &Goban) -> Self {
+        let hash = keys.hash_goban(goban);
+        RunningHash { keys, hash }
+    }
+
+    #[inline]
+    pub fn keys(&self) -> &Arc<ZobristKeys<H>> {
+        &self.keys
+    }
+
+    #[inline]
+    pub fn hash(&self) -> H {
+        self.hash
+    }
+
+    /// Puts or removes the stone on the index of the board.
+    #[inline]
+    pub fn toggle(&mut self, idx: usize, color: Color) {
+        self.hash ^= self.keys.stone_idx(idx, color);
+    }
+}
+
+impl<H: Debug> Debug for RunningHash<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.hash)
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use super::index_zobrist;
+    use super::{index_zobrist, ZobristKeys};
+    use crate::pieces::goban::Goban;
     use crate::pieces::stones::Color;
     use std::collections::HashSet;
 
@@ -751,4 +945,46 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn zobrist_keys() {
+        let keys = ZobristKeys::<u128>::new((13, 19));
+        assert_eq!(keys, ZobristKeys::new((13, 19)));
+        assert_ne!(keys, ZobristKeys::with_seed((13, 19), 1));
+        assert_ne!(
+            keys.stone((12, 18), Color::Black),
+            keys.stone((12, 18), Color::White)
+        );
+        // The last column doesn't alias with the next line
+        assert_ne!(
+            keys.stone((0, 18), Color::Black),
+            keys.stone((1, 0), Color::Black)
+        );
+        assert!(keys.stone((0, 0), Color::Black) >> 64 != 0);
+
+        let keys = ZobristKeys::<u64>::new((9, 13));
+        let mut goban = Goban::new((9, 13));
+        goban.push((4, 12), Color::Black);
+        let hash = keys.hash_goban(&goban);
+        assert_eq!(hash, keys.stone((4, 12), Color::Black));
+        assert_eq!(keys.hash_position(&goban, Color::Black, None), hash);
+        assert_ne!(keys.hash_position(&goban, Color::White, None), hash);
+        assert_ne!(keys.hash_position(&goban, Color::Black, Some((0, 0))), hash);
+        assert_eq!(
+            keys.hash_position(&goban, Color::White, Some((8, 0))),
+            hash ^ keys.white_to_move() ^ keys.ko((8, 0))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn zobrist_keys_outside() {
+        ZobristKeys::<u64>::new((9, 13)).stone((0, 13), Color::Black);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zobrist_keys_other_size() {
+        ZobristKeys::<u64>::new((13, 9)).hash_goban(&Goban::new((9, 13)));
+    }
 }
@@ -0,0 +1,468 @@
+//! Module for the time controls of a game.
+//! # Example
+//! ```
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use goban::rules::clock::{GameClock, ManualTime, TimeControl};
+//! use goban::rules::game::Game;
+//! use goban::rules::{EndGame, Move};
+//! use goban::pieces::stones::Color;
+//!
+//! let time = Arc::new(ManualTime::default());
+//! let mut game = Game::default();
+//! game.set_clock(GameClock::new(
+//!     TimeControl::Absolute { main_time: Duration::from_secs(60) },
+//!     time.clone(),
+//! ));
+//! time.advance(Duration::from_secs(10));
+//! game.play(Move::Play(3, 3));
+//! assert_eq!(game.time_left(Color::Black).unwrap().main_time, Duration::from_secs(50));
+//!
+//! time.advance(Duration::from_secs(61));
+//! assert_eq!(game.check_time(), Some(EndGame::WinnerByTime(Color::Black)));
+//! ```
+
+use crate::pieces::stones::Color;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Source of the time used by the clocks.
+pub trait TimeSource: Debug + Send + Sync {
+    /// Returns the time elapsed since an arbitrary fixed point, it must never decrease.
+    fn now(&self) -> Duration;
+}
+
+/// Time source using the monotonic clock of the system.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemTime {
+    start: Instant,
+}
+
+impl Default for SystemTime {
+    fn default() -> Self {
+        SystemTime {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Time source advanced by hand. Useful for tests and for replaying games with known timings.
+#[derive(Debug, Default)]
+pub struct ManualTime {
+    nanos: AtomicU64,
+}
+
+impl ManualTime {
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// The time system used in the game, each player has the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    /// Sudden death, the player loses when the main time is over.
+    Absolute { main_time: Duration },
+    /// Japanese byo-yomi, after the main time the player has `periods` periods of `period_time`.
+    /// A period is consumed only if a move takes more than `period_time`.
+    ByoYomi {
+        main_time: Duration,
+        period_time: Duration,
+        periods: u32,
+    },
+    /// Canadian overtime, after the main time the player must play `stones` moves in each
+    /// `period_time`.
+    Canadian {
+        main_time: Duration,
+        period_time: Duration,
+        stones: u32,
+    },
+    /// Fischer, `increment` is added to the time of the player after each of his moves. The time
+    /// cannot go above `max_time` if any.
+    Fischer {
+        main_time: Duration,
+        increment: Duration,
+        max_time: Option<Duration>,
+    },
+}
+
+impl TimeControl {
+    /// State of the clock of a player at the beginning of the game.
+    pub fn initial_state(&self) -> ClockState {
+        match *self {
+            TimeControl::Absolute { main_time } | TimeControl::Fischer { main_time, .. } => {
+                ClockState {
+                    main_time,
+                    ..Default::default()
+                }
+            }
+            TimeControl::ByoYomi {
+                main_time,
+                period_time,
+                periods,
+            } => ClockState {
+                main_time,
+                period_time,
+                periods,
+                ..Default::default()
+            },
+            TimeControl::Canadian {
+                main_time,
+                period_time,
+                stones,
+            } => ClockState {
+                main_time,
+                period_time,
+                stones,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Time left for a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClockState {
+    /// Main time left.
+    pub main_time: Duration,
+    /// Time left in the current overtime period (byo-yomi and canadian).
+    pub period_time: Duration,
+    /// Byo-yomi periods left, including the current one.
+    pub periods: u32,
+    /// Stones left to play in the current canadian period.
+    pub stones: u32,
+    /// True if the player ran out of time.
+    pub flagged: bool,
+}
+
+impl ClockState {
+    /// True if the main time is over and the player is in his overtime periods.
+    #[inline]
+    pub fn in_overtime(&self) -> bool {
+        self.main_time.is_zero() && !self.period_time.is_zero()
+    }
+
+    /// Consumes the `elapsed` time, returns false if the player ran out of time.
+    fn spend(&mut self, control: &TimeControl, mut elapsed: Duration) -> bool {
+        if self.flagged {
+            return false;
+        }
+        if elapsed <= self.main_time {
+            self.main_time -= elapsed;
+            return true;
+        }
+        elapsed -= self.main_time;
+        self.main_time = Duration::ZERO;
+        match *control {
+            TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => {
+                self.flagged = true;
+            }
+            TimeControl::ByoYomi { period_time, .. } => loop {
+                if self.periods == 0 {
+                    self.flagged = true;
+                    self.period_time = Duration::ZERO;
+                    break;
+                }
+                if elapsed <= self.period_time {
+                    self.period_time -= elapsed;
+                    break;
+                }
+                elapsed -= self.period_time;
+                self.periods -= 1;
+                self.period_time = if self.periods == 0 {
+                    Duration::ZERO
+                } else {
+                    period_time
+                };
+            },
+            TimeControl::Canadian { .. } => {
+                if elapsed <= self.period_time {
+                    self.period_time -= elapsed;
+                } else {
+                    self.period_time = Duration::ZERO;
+                    self.flagged = true;
+                }
+            }
+        }
+        !self.flagged
+    }
+
+    /// Updates the clock after a move of the player.
+    fn moved(&mut self, control: &TimeControl) {
+        match *control {
+            TimeControl::Absolute { .. } => {}
+            TimeControl::ByoYomi { period_time, .. } => {
+                if self.main_time.is_zero() && self.periods > 0 {
+                    self.period_time = period_time;
+                }
+            }
+            TimeControl::Canadian {
+                period_time,
+                stones,
+                ..
+            } => {
+                if self.main_time.is_zero() {
+                    self.stones = self.stones.saturating_sub(1);
+                    if self.stones == 0 {
+                        self.stones = stones;
+                        self.period_time = period_time;
+                    }
+                }
+            }
+            TimeControl::Fischer {
+                increment,
+                max_time,
+                ..
+            } => {
+                self.main_time += increment;
+                if let Some(max_time) = max_time {
+                    self.main_time = self.main_time.min(max_time);
+                }
+            }
+        }
+    }
+}
+
+/// Clocks of the two players of a game.
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    control: TimeControl,
+    source: Arc<dyn TimeSource>,
+    black: ClockState,
+    white: ClockState,
+    /// Instant when the current player started thinking, None if the clock is stopped.
+    turn_start: Option<Duration>,
+}
+
+impl GameClock {
+    /// Creates a stopped clock.
+    pub fn new(control: TimeControl, source: Arc<dyn TimeSource>) -> Self {
+        GameClock {
+            control,
+            source,
+            black: control.initial_state(),
+            white: control.initial_state(),
+            turn_start: None,
+        }
+    }
+
+    /// Creates a stopped clock using the time of the system.
+    pub fn with_system_time(control: TimeControl) -> Self {
+        Self::new(control, Arc::new(SystemTime::default()))
+    }
+
+    #[inline]
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.turn_start.is_some()
+    }
+
+    /// Starts counting the time of the player to move.
+    pub fn start(&mut self) {
+        if self.turn_start.is_none() {
+            self.turn_start = Some(self.source.now());
+        }
+    }
+
+    /// Charges the time spent to `to_move` and stops the clock.
+    pub fn stop(&mut self, to_move: Color) {
+        let elapsed = self.elapsed();
+        let control = self.control;
+        self.state_mut(to_move).spend(&control, elapsed);
+        self.turn_start = None;
+    }
+
+    /// State of the clock of the player, the time spent in the current turn is counted if
+    /// `to_move` is the player.
+    pub fn state(&self, color: Color, to_move: Color) -> ClockState {
+        let mut state = *self.state_ref(color);
+        if color == to_move {
+            state.spend(&self.control, self.elapsed());
+        }
+        state
+    }
+
+    /// Returns true if the player to move ran out of time.
+    pub fn is_flagged(&self, to_move: Color) -> bool {
+        self.state(to_move, to_move).flagged
+    }
+
+    /// Ends the turn of the player who moved. Returns false if he ran out of time before moving.
+    pub fn punch(&mut self, moved: Color) -> bool {
+        let now = self.source.now();
+        let elapsed = self.elapsed_at(now);
+        let control = self.control;
+        let state = self.state_mut(moved);
+        let in_time = state.spend(&control, elapsed);
+        if in_time {
+            state.moved(&control);
+        }
+        if self.turn_start.is_some() {
+            self.turn_start = Some(now);
+        }
+        in_time
+    }
+
+    /// Sets the time left of a player like the GTP command `time_left`.
+    /// If `stones` is 0 the time is the main time, else it's the time of the current overtime
+    /// period.
+    pub fn set_time_left(&mut self, color: Color, time: Duration, stones: u32) {
+        let is_canadian = matches!(self.control, TimeControl::Canadian { .. });
+        let state = self.state_mut(color);
+        if stones == 0 {
+            state.main_time = time;
+        } else {
+            state.main_time = Duration::ZERO;
+            state.period_time = time;
+            if is_canadian {
+                state.stones = stones;
+            }
+        }
+        state.flagged = false;
+        if self.turn_start.is_some() {
+            self.turn_start = Some(self.source.now());
+        }
+    }
+
+    /// Returns the time left in seconds and the number of stones to play in this time, as
+    /// expected by the GTP command `time_left`. The number of stones is 0 while in main time.
+    pub fn gtp_time_left(&self, state: &ClockState) -> (u64, u32) {
+        if !state.main_time.is_zero() || !state.in_overtime() {
+            return (state.main_time.as_secs(), 0);
+        }
+        match self.control {
+            TimeControl::ByoYomi { .. } => (state.period_time.as_secs(), 1),
+            TimeControl::Canadian { .. } => (state.period_time.as_secs(), state.stones),
+            TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => (0, 0),
+        }
+    }
+
+    #[inline]
+    fn elapsed(&self) -> Duration {
+        self.elapsed_at(self.source.now())
+    }
+
+    #[inline]
+    fn elapsed_at(&self, now: Duration) -> Duration {
+        self.turn_start
+            .map_or(Duration::ZERO, |start| now.saturating_sub(start))
+    }
+
+    #[inline]
+    fn state_ref(&self, color: Color) -> &ClockState {
+        match color {
+            Color::Black => &self.black,
+            Color::White => &self.white,
+        }
+    }
+
+    #[inline]
+    fn state_mut(&mut self, color: Color) -> &mut ClockState {
+        match color {
+            Color::Black => &mut self.black,
+            Color::White => &mut self.white,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn clock(control: TimeControl) -> (GameClock, Arc<ManualTime>) {
+        let time = Arc::new(ManualTime::default());
+        let mut clock = GameClock::new(control, time.clone());
+        clock.start();
+        (clock, time)
+    }
+
+    #[test]
+    fn byo_yomi() {
+        let (mut clock, time) = clock(TimeControl::ByoYomi {
+            main_time: secs(10),
+            period_time: secs(5),
+            periods: 3,
+        });
+        time.advance(secs(12));
+        assert!(clock.punch(Color::Black));
+        let state = clock.state(Color::Black, Color::White);
+        assert_eq!(state.main_time, Duration::ZERO);
+        assert_eq!((state.period_time, state.periods), (secs(5), 3));
+        assert_eq!(clock.gtp_time_left(&state), (5, 1));
+
+        // White plays instantly, black uses one period and a half.
+        clock.punch(Color::White);
+        time.advance(secs(8));
+        assert!(clock.punch(Color::Black));
+        let state = clock.state(Color::Black, Color::White);
+        assert_eq!((state.period_time, state.periods), (secs(5), 2));
+
+        clock.punch(Color::White);
+        time.advance(secs(11));
+        assert!(!clock.punch(Color::Black));
+        assert!(clock.state(Color::Black, Color::White).flagged);
+    }
+
+    #[test]
+    fn canadian() {
+        let (mut clock, time) = clock(TimeControl::Canadian {
+            main_time: Duration::ZERO,
+            period_time: secs(10),
+            stones: 2,
+        });
+        time.advance(secs(4));
+        assert!(clock.punch(Color::Black));
+        let state = clock.state(Color::Black, Color::White);
+        assert_eq!((state.period_time, state.stones), (secs(6), 1));
+        assert_eq!(clock.gtp_time_left(&state), (6, 1));
+
+        clock.punch(Color::White);
+        time.advance(secs(5));
+        assert!(clock.punch(Color::Black));
+        let state = clock.state(Color::Black, Color::White);
+        assert_eq!((state.period_time, state.stones), (secs(10), 2));
+
+        clock.punch(Color::White);
+        time.advance(secs(11));
+        assert!(clock.is_flagged(Color::Black));
+    }
+
+    #[test]
+    fn fischer() {
+        let (mut clock, time) = clock(TimeControl::Fischer {
+            main_time: secs(10),
+            increment: secs(5),
+            max_time: Some(secs(12)),
+        });
+        time.advance(secs(1));
+        assert!(clock.punch(Color::Black));
+        assert_eq!(clock.state(Color::Black, Color::White).main_time, secs(12));
+        time.advance(secs(3));
+        assert_eq!(clock.state(Color::White, Color::White).main_time, secs(7));
+        assert!(clock.punch(Color::White));
+        assert_eq!(clock.state(Color::White, Color::Black).main_time, secs(12));
+    }
+}
@@ -1,6 +1,7 @@
 use rand::seq::SliceRandom;
 use std::collections::{HashMap, HashSet};
 
+use crate::pieces::chain::ChainId;
 use crate::pieces::goban::GroupIdx;
 use crate::pieces::stones::Stone;
 use crate::rules::game::Game;
@@ -130,38 +131,32 @@ type Mcts<'a> = LazyMcts<
 >;
 
 impl Game {
-    /// This return the groups that doesn't have two eyes
+    /// This return the groups that doesn't have two eyes.
+    /// See [`Goban::count_eyes`](crate::pieces::goban::Goban::count_eyes).
     pub fn get_floating_stones(&self) -> Vec<GroupIdx> {
-        let eyes = self.pseudo_legals().filter(|&p| {
-            self.check_eye(Stone {
-                coord: p,
-                color: Color::Black,
-            }) || self.check_eye(Stone {
-                coord: p,
-                color: Color::White,
-            })
-        });
-        let mut chains_wth_eye = HashMap::new();
-        for eye_coord in eyes {
-            let chain_connected_eye = self.goban.get_connected_groups_idx(eye_coord);
-            for chain_idx in chain_connected_eye {
-                chains_wth_eye
-                    .entry(chain_idx)
-                    .and_modify(|v| *v += 1)
-                    .or_insert(0);
+        let mut eyes_by_chain: HashMap<ChainId, usize> = HashMap::new();
+        for eye in self
+            .goban
+            .eyes(Color::Black)
+            .into_iter()
+            .chain(self.goban.eyes(Color::White))
+        {
+            for &chain_id in &eye.chains {
+                *eyes_by_chain.entry(chain_id).or_insert(0) += eye.value();
             }
         }
-        let all_chains = self.goban.chains().enumerate();
-        let string_with_2eyes = chains_wth_eye
-            .into_iter()
-            .filter(|(_, v)| *v >= 2)
-            .map(|x| x.0)
-            .collect::<HashSet<_>>();
-
-        all_chains
-            .filter(|(idx, _chain)| string_with_2eyes.contains(idx))
-            .map(|(idx, _chain)| idx)
-            .collect()
+        let mut floating: Vec<GroupIdx> = self
+            .goban
+            .get_stones()
+            .filter(|stone| {
+                let id = self.goban.get_chain(stone.coord).unwrap().id;
+                eyes_by_chain.get(&id).copied().unwrap_or(0) < 2
+            })
+            .filter_map(|stone| self.goban.get_chain_idx(stone.coord))
+            .collect();
+        floating.sort_unstable();
+        floating.dedup();
+        floating
     }
 
     pub fn dead_stones_wth_simulations(&self, nb_simulations: usize) -> HashSet<GroupIdx> {
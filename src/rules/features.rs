@@ -0,0 +1,418 @@
+//! Module for creating the input of neural networks from a game.
+//!
+//! The planes are written one after another, each plane is in row major order with the
+//! point (0,0) first. So the value of the point `(line, column)` of the plane `p` is at
+//! `p * height * width + line * width + column`. With a symmetry the points are moved before
+//! being written, for rotations and transpositions on a non square goban the height and the
+//! width are swapped.
+//!
+//! The planes "own" and "opponent" are relative to the player to move.
+//!
+//! # Example
+//! ```
+//! use goban::rules::features::{Features, Plane, Symmetry};
+//! use goban::rules::game::Game;
+//! use goban::rules::Move;
+//!
+//! let mut game = Game::default();
+//! game.play(Move::Play(3, 3));
+//! let features = Features::new(&[Plane::OwnStones(0), Plane::OpponentStones(0), Plane::Legal])
+//!     .with_symmetry(Symmetry::Rotate90);
+//! let mut input = vec![0f32; features.len(game.size())];
+//! features.fill(&game, &mut input);
+//! // The black stone is an opponent stone for white.
+//! assert_eq!(input[361 + 3 * 19 + 15], 1.);
+//! ```
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::{Color, Stone};
+use crate::pieces::util::coord::{Coord, Size};
+use crate::rules::game::Game;
+
+/// A plane of features, there is one value by point of the goban, 1 if the feature is present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Plane {
+    /// Stones of the player to move, `n` moves ago. 0 is the current position.
+    /// The plane is empty if the game doesn't have enough moves.
+    OwnStones(u8),
+    /// Stones of the opponent, `n` moves ago.
+    OpponentStones(u8),
+    /// Empty points.
+    Empty,
+    /// Stones of the chains with one liberty.
+    OneLiberty,
+    /// Stones of the chains with two liberties.
+    TwoLiberties,
+    /// Stones of the chains with three liberties or more.
+    ThreeLibertiesOrMore,
+    /// The point forbidden by the ko for the player to move.
+    KoPoint,
+    /// Legal moves of the player to move, following the rule of the game.
+    Legal,
+    /// Filled with ones if black is to move.
+    BlackToMove,
+    /// Filled with ones if white is to move.
+    WhiteToMove,
+    /// Filled with ones, it shows the edges of the goban to the network.
+    Ones,
+    /// Stones of the chains in atari that are captured by a ladder.
+    LadderCaptured,
+    /// Moves of the player to move that capture an opponent chain with a ladder.
+    LadderCapture,
+    /// Moves of the player to move that save one of his chains in atari from a ladder.
+    LadderEscape,
+}
+
+/// The 8 symmetries of the goban.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Symmetry {
+    #[default]
+    Identity,
+    /// Rotation of 90° clockwise.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// The lines are reversed.
+    FlipVertical,
+    /// The columns are reversed.
+    FlipHorizontal,
+    /// The lines become the columns.
+    Transpose,
+    /// Transposition by the other diagonal.
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipVertical,
+        Symmetry::FlipHorizontal,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// Size of the goban after the symmetry.
+    #[inline]
+    pub const fn size(self, (height, width): Size) -> Size {
+        match self {
+            Symmetry::Rotate90
+            | Symmetry::Rotate270
+            | Symmetry::Transpose
+            | Symmetry::AntiTranspose => (width, height),
+            _ => (height, width),
+        }
+    }
+
+    /// Moves a point of a goban of size `size`, the point is on a goban of size
+    /// [`Symmetry::size`] after it.
+    pub const fn apply(self, (line, column): Coord, (height, width): Size) -> Coord {
+        let last_line = height - 1;
+        let last_column = width - 1;
+        match self {
+            Symmetry::Identity => (line, column),
+            Symmetry::Rotate90 => (column, last_line - line),
+            Symmetry::Rotate180 => (last_line - line, last_column - column),
+            Symmetry::Rotate270 => (last_column - column, line),
+            Symmetry::FlipVertical => (last_line - line, column),
+            Symmetry::FlipHorizontal => (line, last_column - column),
+            Symmetry::Transpose => (column, line),
+            Symmetry::AntiTranspose => (last_column - column, last_line - line),
+        }
+    }
+
+    /// The symmetry that cancels this one. On a non square goban it's applied with the size
+    /// after this symmetry, see [`Symmetry::revert`].
+    pub const fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => other,
+        }
+    }
+
+    /// Moves back a point given by [`Symmetry::apply`] on a goban of size `size`, for example a
+    /// move of a policy computed on the transformed planes.
+    #[inline]
+    pub const fn revert(self, coord: Coord, size: Size) -> Coord {
+        self.inverse().apply(coord, self.size(size))
+    }
+}
+
+/// Type of the values written in the buffers.
+pub trait FeatureValue: Copy {
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+impl FeatureValue for f32 {
+    const ZERO: Self = 0.;
+    const ONE: Self = 1.;
+}
+
+impl FeatureValue for u8 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+}
+
+/// List of the planes given to a network, with the symmetry applied to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Features {
+    planes: Vec<Plane>,
+    symmetry: Symmetry,
+}
+
+impl Features {
+    pub fn new(planes: &[Plane]) -> Self {
+        Features {
+            planes: planes.to_vec(),
+            symmetry: Symmetry::Identity,
+        }
+    }
+
+    /// Planes close to the ones of AlphaGo Zero: the stones of the last `history` positions for
+    /// each player then the player to move.
+    pub fn alphago_zero(history: u8) -> Self {
+        let mut planes = vec![];
+        for n in 0..history {
+            planes.push(Plane::OwnStones(n));
+            planes.push(Plane::OpponentStones(n));
+        }
+        planes.push(Plane::BlackToMove);
+        Features::new(&planes)
+    }
+
+    pub fn with_symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    #[inline]
+    pub fn planes(&self) -> &[Plane] {
+        &self.planes
+    }
+
+    #[inline]
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Length of the buffer needed for a goban of this size.
+    #[inline]
+    pub fn len(&self, (height, width): Size) -> usize {
+        self.planes.len() * height as usize * width as usize
+    }
+
+    /// Fills the buffer with the planes of the game, all the buffer is overwritten.
+    ///
+    /// # Panics
+    ///
+    /// If the length of the buffer isn't [`Features::len`].
+    pub fn fill<T: FeatureValue>(&self, game: &Game, buffer: &mut [T]) {
+        let size = game.size();
+        assert_eq!(
+            buffer.len(),
+            self.len(size),
+            "The buffer doesn't have the size of the planes"
+        );
+        buffer.fill(T::ZERO);
+        let history = self
+            .planes
+            .iter()
+            .filter_map(|plane| match plane {
+                Plane::OwnStones(n) | Plane::OpponentStones(n) => Some(*n as u32),
+                _ => None,
+            })
+            .max()
+            .map_or(vec![], |n| game.last_gobans(n));
+        let plane_len = size.0 as usize * size.1 as usize;
+        let (_, out_width) = self.symmetry.size(size);
+        for (plane, values) in self.planes.iter().zip(buffer.chunks_exact_mut(plane_len)) {
+            let mut set = |coord: Coord| {
+                let (line, column) = self.symmetry.apply(coord, size);
+                values[line as usize * out_width as usize + column as usize] = T::ONE;
+            };
+            fill_plane(game, &history, *plane, &mut set);
+        }
+    }
+}
+
+/// `history` has the current goban then the gobans after the last moves, for the stones planes.
+fn fill_plane(game: &Game, history: &[Goban], plane: Plane, set: &mut impl FnMut(Coord)) {
+    let turn = game.turn();
+    match plane {
+        Plane::OwnStones(n) => stones_ago(history, n, turn, set),
+        Plane::OpponentStones(n) => stones_ago(history, n, !turn, set),
+        Plane::Empty => game.get_empty_coords().for_each(set),
+        Plane::OneLiberty => liberties(game, |l| l == 1, set),
+        Plane::TwoLiberties => liberties(game, |l| l == 2, set),
+        Plane::ThreeLibertiesOrMore => liberties(game, |l| l >= 3, set),
+        Plane::KoPoint => {
+            if let Some(coord) = game.ko_point() {
+                if game.check_ko(Stone { coord, color: turn }) {
+                    set(coord);
+                }
+            }
+        }
+        Plane::Legal => game.legals().for_each(set),
+        Plane::BlackToMove | Plane::WhiteToMove => {
+            let color = if plane == Plane::BlackToMove {
+                Color::Black
+            } else {
+                Color::White
+            };
+            if turn == color {
+                all_points(game.size()).for_each(set);
+            }
+        }
+        Plane::Ones => all_points(game.size()).for_each(set),
+        Plane::LadderCaptured => {
+            for stone in game.get_stones() {
+                let in_atari = game.get_chain(stone.coord).is_some_and(|c| c.is_atari());
+                if in_atari && game.is_ladder_captured(stone.coord) {
+                    set(stone.coord);
+                }
+            }
+        }
+        Plane::LadderCapture => {
+            for coord in game.legals() {
+                if game.is_ladder_capture(coord, turn) {
+                    set(coord);
+                }
+            }
+        }
+        Plane::LadderEscape => {
+            for coord in game.legals() {
+                if game.is_ladder_escape(coord, turn) {
+                    set(coord);
+                }
+            }
+        }
+    }
+}
+
+fn all_points((height, width): Size) -> impl Iterator<Item = Coord> {
+    (0..height).flat_map(move |line| (0..width).map(move |column| (line, column)))
+}
+
+fn stones_ago(history: &[Goban], n: u8, color: Color, set: &mut impl FnMut(Coord)) {
+    if let Some(goban) = history.get(n as usize) {
+        stones_of(goban, color, set);
+    }
+}
+
+fn stones_of(goban: &Goban, color: Color, set: &mut impl FnMut(Coord)) {
+    goban
+        .get_stones()
+        .filter(|s| s.color == color)
+        .for_each(|s| set(s.coord));
+}
+
+fn liberties(game: &Game, filter: impl Fn(usize) -> bool, set: &mut impl FnMut(Coord)) {
+    for stone in game.get_stones() {
+        let libs = game.get_chain(stone.coord).unwrap().number_of_liberties();
+        if filter(libs) {
+            set(stone.coord);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Move;
+
+    #[test]
+    fn symmetries() {
+        let size = (19, 19);
+        for symmetry in Symmetry::ALL {
+            let coord = symmetry.apply((2, 5), size);
+            assert_eq!(symmetry.inverse().apply(coord, size), (2, 5));
+        }
+        assert_eq!(Symmetry::Rotate90.apply((0, 0), size), (0, 18));
+        assert_eq!(Symmetry::Transpose.apply((2, 5), size), (5, 2));
+
+        // 5 lines of 9 columns
+        let size = (5, 9);
+        for symmetry in Symmetry::ALL {
+            let (height, width) = symmetry.size(size);
+            for line in 0..5 {
+                for column in 0..9 {
+                    let coord = symmetry.apply((line, column), size);
+                    assert!(coord.0 < height && coord.1 < width);
+                    assert_eq!(symmetry.revert(coord, size), (line, column));
+                }
+            }
+        }
+        assert_eq!(Symmetry::Rotate90.apply((0, 0), size), (0, 4));
+        assert_eq!(Symmetry::Rotate270.apply((0, 0), size), (8, 0));
+    }
+
+    #[test]
+    fn planes() {
+        let mut game = Game::default();
+        game.play(Move::Play(0, 0)).play(Move::Play(0, 1));
+        let features = Features::new(&[
+            Plane::OwnStones(0),
+            Plane::OpponentStones(0),
+            Plane::OwnStones(1),
+            Plane::OneLiberty,
+            Plane::BlackToMove,
+            Plane::WhiteToMove,
+        ]);
+        let mut buffer = vec![0u8; features.len(game.size())];
+        features.fill(&game, &mut buffer);
+        let plane = |p: usize| &buffer[p * 361..(p + 1) * 361];
+        assert_eq!(plane(0).iter().filter(|&&v| v == 1).count(), 1);
+        assert_eq!(plane(0)[0], 1);
+        assert_eq!(plane(1)[1], 1);
+        // One move ago black only had the stone on (0,0)
+        assert_eq!(plane(2)[0], 1);
+        assert_eq!(plane(2).iter().filter(|&&v| v == 1).count(), 1);
+        assert_eq!(plane(3)[0], 1);
+        assert_eq!(plane(3)[1], 0);
+        assert!(plane(4).iter().all(|&v| v == 1));
+        assert!(plane(5).iter().all(|&v| v == 0));
+
+        // The rotated planes of a non square goban are 9 lines of 5 columns
+        let mut game = Game::builder().size((5, 9)).build().unwrap();
+        game.play(Move::Play(1, 7));
+        let features = Features::new(&[Plane::OpponentStones(0)]).with_symmetry(Symmetry::Rotate90);
+        let mut buffer = vec![0u8; features.len(game.size())];
+        features.fill(&game, &mut buffer);
+        let (line, column) = Symmetry::Rotate90.apply((1, 7), (5, 9));
+        assert_eq!((line, column), (7, 3));
+        assert_eq!(buffer[line as usize * 5 + column as usize], 1);
+        assert_eq!(buffer.iter().filter(|&&v| v == 1).count(), 1);
+    }
+
+    #[test]
+    fn ladders() {
+        let mut game = Game::default();
+        game.add_stones(&[Stone {
+            coord: (4, 4),
+            color: Color::White,
+        }]);
+        for coord in [(3, 4), (4, 3), (5, 5)] {
+            game.add_stones(&[Stone {
+                coord,
+                color: Color::Black,
+            }]);
+        }
+        let features = Features::new(&[Plane::LadderCapture]);
+        let mut buffer = vec![0f32; features.len(game.size())];
+        features.fill(&game, &mut buffer);
+        assert_eq!(buffer[5 * 19 + 4], 1.);
+        assert_eq!(buffer[0], 0.);
+
+        game.play(Move::Play(5, 4));
+        let features = Features::new(&[Plane::LadderCaptured, Plane::LadderEscape]);
+        let mut buffer = vec![0f32; features.len(game.size())];
+        features.fill(&game, &mut buffer);
+        assert_eq!(buffer[4 * 19 + 4], 1.);
+        // Running doesn't save the stone
+        assert_eq!(buffer[361 + 4 * 19 + 5], 0.);
+    }
+}
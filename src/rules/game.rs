@@ -1,16 +1,20 @@
 use crate::pieces::goban::*;
 use crate::pieces::stones::Color::{Black, White};
 use crate::pieces::stones::{Color, Stone, EMPTY};
-use crate::pieces::util::coord::{corner_points, is_coord_valid, two_to_1dim, Coord, Size};
-use crate::pieces::{Connections, Nat};
+use crate::pieces::util::coord::{is_coord_valid, two_to_1dim, Coord, Size};
+use crate::pieces::zobrist::{ZobristHash, ZobristKeys};
+use crate::pieces::Nat;
 use crate::rules::EndGame::{Draw, WinnerByScore};
+use crate::rules::clock::{ClockState, GameClock};
+use crate::rules::game_info::GameInfo;
+use crate::rules::record::{MoveLog, MoveRecord, SetupRecord};
+use crate::rules::super_ko::{pack, PositionHistory, SuperKoCheck};
 use crate::rules::Rule;
 use crate::rules::{EndGame, GobanSizes, IllegalRules, Move, ScoreRules};
 use crate::rules::{PlayError, CHINESE};
-use hash_hasher::{HashBuildHasher, HashHasher};
-use indexmap::IndexSet;
-use std::hash::BuildHasherDefault;
 use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(feature = "deadstones")]
 pub mod dead_stones;
@@ -18,6 +22,9 @@ pub mod game_builder;
 
 /// Entrypoint struct of the library.
 /// It represents a Game of Go.
+///
+/// A game is `Send` and `Sync`: its copies share the old moves and positions behind an `Arc`,
+/// and can be played on other threads.
 #[derive(Clone, Debug)]
 pub struct Game {
     goban: Goban,
@@ -28,10 +35,18 @@ pub struct Game {
     turn: Color,
     rule: Rule,
     handicap: u32,
-    history: IndexSet<Goban, BuildHasherDefault<HashHasher>>,
+    history: PositionHistory,
     ko_point: Option<Coord>,
+    record: MoveLog,
+    clock: Option<GameClock>,
+    info: Arc<GameInfo>,
 }
 
+const _: () = {
+    fn assert<T: Send + Sync>() {}
+    let _ = assert::<Game>;
+};
+
 impl Deref for Game {
     type Target = Goban;
 
@@ -43,12 +58,10 @@ impl Deref for Game {
 impl Game {
     /// Crates a new game for playing Go
     pub fn new(size: GobanSizes, rule: Rule) -> Self {
-        let (h, w) = size.into();
         let goban = Goban::new(size.into());
-        let length = h as usize * w as usize;
         let prisoners = (0, 0);
         let handicap = 0;
-        let history = IndexSet::with_capacity_and_hasher(length, HashBuildHasher::default());
+        let history = PositionHistory::default();
         Self {
             goban,
             turn: Color::Black,
@@ -59,6 +72,9 @@ impl Game {
             handicap,
             history,
             ko_point: None,
+            record: MoveLog::default(),
+            clock: None,
+            info: Arc::default(),
         }
     }
 }
@@ -68,6 +84,12 @@ impl Game {
     #[inline]
     pub fn resume(&mut self) {
         self.passes = 0;
+        if self.outcome.is_some() {
+            return;
+        }
+        if let Some(clock) = &mut self.clock {
+            clock.start();
+        }
     }
 
     #[inline]
@@ -97,9 +119,207 @@ impl Game {
         self.turn
     }
 
+    /// Point of the last single stone captured, the ko is checked on it.
+    #[inline]
+    pub fn ko_point(&self) -> Option<Coord> {
+        self.ko_point
+    }
+
+    /// Hash of the position with the keys, the player to move and the ko point included.
+    /// It's computed from the stones on each call, see [`Game::wide_position_hash`] for a
+    /// hash kept during the game.
+    ///
+    /// # Panics
+    /// If the keys are for another size.
+    #[inline]
+    pub fn position_hash<H: ZobristHash>(&self, keys: &ZobristKeys<H>) -> H {
+        keys.hash_position(&self.goban, self.turn, self.ko_point)
+    }
+
+    /// Hash of the position with the 128 bits keys of the game, the player to move and the ko
+    /// point included. It's updated with the moves, so it costs nothing to get.
+    /// None if the game has no wide keys, see [`Game::set_wide_keys`].
+    #[inline]
+    pub fn wide_position_hash(&self) -> Option<u128> {
+        let keys = self.goban.wide_keys()?;
+        let hash = self.goban.wide_hash()?;
+        Some(keys.with_turn_and_ko(hash, self.turn, self.ko_point))
+    }
+
+    /// Keeps a 128 bits hash of the position with the keys during the game, for the
+    /// transposition tables and the [`SuperKoCheck::WideHash`] super ko.
+    ///
+    /// # Panics
+    /// If the keys are for another size.
+    pub fn set_wide_keys(&mut self, keys: Arc<ZobristKeys<u128>>) -> &mut Self {
+        self.goban.set_wide_keys(keys);
+        self
+    }
+
+    #[inline]
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Number of handicap stones.
+    #[inline]
+    pub fn handicap(&self) -> u32 {
+        self.handicap
+    }
+
+    /// Information about the game: players, event, date...
+    #[inline]
+    pub fn info(&self) -> &GameInfo {
+        &self.info
+    }
+
+    #[inline]
+    pub fn info_mut(&mut self) -> &mut GameInfo {
+        Arc::make_mut(&mut self.info)
+    }
+
+    /// Returns the moves played in the game.
+    #[inline]
+    pub fn record(&self) -> &MoveLog {
+        &self.record
+    }
+
+    /// Returns the last move played, None if no moves were played.
+    #[inline]
+    pub fn last_move(&self) -> Option<&MoveRecord> {
+        self.record.last()
+    }
+
+    /// Returns the goban as it was after the move `number`, before the setups that followed it.
+    /// The number 0 gives the goban at the start of the game.
+    /// It's computed by undoing the record from the current goban.
+    pub fn goban_at(&self, number: u32) -> Goban {
+        let number = number.min(self.record.len() as u32);
+        let mut found = None;
+        self.walk_back(|move_number, goban| {
+            if move_number <= number {
+                found = Some(goban.clone());
+            }
+            found.is_none()
+        });
+        found.unwrap()
+    }
+
+    /// Returns the current goban then the gobans after each of the last `n` moves, the most
+    /// recent first: the goban `i > 0` is [`Game::goban_at`] the move `record().len() - i`.
+    /// There are fewer gobans if less than `n` moves were played.
+    /// The record is undone only once, for all the gobans.
+    pub fn last_gobans(&self, n: u32) -> Vec<Goban> {
+        let played = self.record.len() as u32;
+        let oldest = played.saturating_sub(n);
+        let mut gobans = vec![self.goban.clone()];
+        self.walk_back(|move_number, goban| {
+            if move_number < played {
+                gobans.push(goban.clone());
+            }
+            move_number > oldest
+        });
+        gobans
+    }
+
+    /// Undoes the record from the current goban, `visit` gets the goban after each move from the
+    /// last to the first, then the goban at the start of the game with the number 0. The walk
+    /// stops when `visit` returns false.
+    fn walk_back(&self, mut visit: impl FnMut(u32, &Goban) -> bool) {
+        let mut goban = self.goban.clone();
+        let mut setups = self.record.setups().iter().rev().peekable();
+        for record in self.record.iter().rev() {
+            while let Some(setup) = setups.next_if(|s| s.after_move >= record.number) {
+                undo_setup(&mut goban, setup);
+            }
+            if !visit(record.number, &goban) {
+                return;
+            }
+            if let Move::Play(x, y) = record.play {
+                // The captured stones go back first, in case of suicide the played stone is one of
+                // them.
+                for &stone in self.record.captures(record) {
+                    goban.push_stone(stone);
+                }
+                goban.remove_stone((x, y));
+            }
+        }
+        for setup in setups {
+            undo_setup(&mut goban, setup);
+        }
+        visit(0, &goban);
+    }
+
+    /// Attaches clocks to the game, the time of the player to move starts to run.
+    pub fn set_clock(&mut self, mut clock: GameClock) {
+        if !self.is_over() {
+            clock.start();
+        }
+        self.clock = Some(clock);
+    }
+
+    #[inline]
+    pub fn clock(&self) -> Option<&GameClock> {
+        self.clock.as_ref()
+    }
+
+    /// Returns the time left of the player, counting the time spent in the current turn.
+    /// None if the game doesn't have clocks.
+    pub fn time_left(&self, color: Color) -> Option<ClockState> {
+        self.clock.as_ref().map(|clock| clock.state(color, self.turn))
+    }
+
+    /// Returns the time left in seconds and the number of stones to play, in the format of the
+    /// GTP command `time_left`. None if the game doesn't have clocks.
+    pub fn gtp_time_left(&self, color: Color) -> Option<(u64, u32)> {
+        self.clock
+            .as_ref()
+            .map(|clock| clock.gtp_time_left(&clock.state(color, self.turn)))
+    }
+
+    /// Sets the time left of a player like the GTP command `time_left`.
+    /// Does nothing if the game doesn't have clocks.
+    pub fn set_time_left(&mut self, color: Color, time: Duration, stones: u32) {
+        if let Some(clock) = &mut self.clock {
+            clock.set_time_left(color, time, stones);
+        }
+    }
+
+    /// Checks if the player to move ran out of time, then ends the game.
+    /// Returns the outcome if the game is over by time.
+    pub fn check_time(&mut self) -> Option<EndGame> {
+        if self.is_over() {
+            return self.outcome.filter(|o| matches!(o, EndGame::WinnerByTime(_)));
+        }
+        let flagged = self
+            .clock
+            .as_ref()
+            .is_some_and(|clock| clock.is_flagged(self.turn));
+        if flagged {
+            self.lose_on_time();
+        }
+        self.outcome
+    }
+
     #[cfg(feature = "history")]
     pub fn history(&self) -> impl Iterator<Item = &Goban> {
-        self.history.iter()
+        self.history.gobans()
+    }
+
+    /// How the positions are compared for the super ko, only the hashes by default.
+    #[inline]
+    pub fn super_ko_check(&self) -> SuperKoCheck {
+        self.history.check()
+    }
+
+    /// The positions already played are compared in the mode they were added with. The wide
+    /// hash mode sets the default wide keys if the game has none.
+    pub fn set_super_ko_check(&mut self, check: SuperKoCheck) -> &mut Self {
+        if check == SuperKoCheck::WideHash && self.goban.wide_keys().is_none() {
+            self.set_wide_keys(Arc::new(ZobristKeys::new(self.size())));
+        }
+        self.history.set_check(check);
+        self
     }
 
     /// True when the game is over (two passes, or no more legals moves, Resign)
@@ -170,32 +390,52 @@ impl Game {
 
     /// Method to play on the goban or pass.
     /// (0,0) is in the top left corner of the goban.
+    /// `Move::Resign(color)` ends the game, `color` resigns and the opponent wins.
     ///
     /// # Panics
     ///
     /// If the coordinates of the move are outside the board.
+    /// If the game has clocks and the player ran out of time, the move is not played and the
+    /// game is lost by time.
     pub fn play(&mut self, play: Move) -> &mut Self {
+        if !self.punch_clock() {
+            return self;
+        }
+        self.play_after_clock(play)
+    }
+
+    /// Plays the move, the clock is already punched.
+    fn play_after_clock(&mut self, play: Move) -> &mut Self {
         match play {
             Move::Pass => {
                 assert!(self.passes < 2, "This game is already paused");
+                self.record
+                    .push(self.turn, play, None, self.goban.zobrist_hash());
                 self.turn = !self.turn;
                 self.ko_point = None;
                 self.passes += 1;
+                if self.passes >= 2 {
+                    self.stop_clock();
+                }
                 self
             }
             Move::Play(x, y) => {
-                //let hash = self.goban.zobrist_hash();
-                self.history.insert(self.goban.clone());
+                self.history.insert(&self.goban);
                 let (dead_rens, added_ren) = self.goban.push_wth_feedback((x, y), self.turn);
                 self.ko_point = None;
                 self.remove_captured_stones(&dead_rens, added_ren);
                 //self.prisoners = self.remove_captured_stones();
+                self.record
+                    .push(self.turn, play, self.ko_point, self.goban.zobrist_hash());
                 self.turn = !self.turn;
                 self.passes = 0;
                 self
             }
             Move::Resign(player) => {
-                self.outcome = Some(EndGame::WinnerByResign(player));
+                self.record
+                    .push(player, play, None, self.goban.zobrist_hash());
+                self.outcome = Some(EndGame::WinnerByResign(!player));
+                self.stop_clock();
                 self
             }
         }
@@ -210,37 +450,178 @@ impl Game {
             self.rule.flag_illegal.contains(IllegalRules::SUICIDE),
             &dead_go_strings,
             added_ren,
+            None,
         );
         test_goban
     }
 
     /// Method to play but it verifies if the play is legal or not.
+    /// Every move passing this check can be given to [`Game::play`] safely, so it can be used
+    /// directly with untrusted inputs.
     ///
     /// # Errors
     ///
+    /// If the game has an outcome (resign, time...), then return `[PlayError::GameOver]`,
+    /// the game is lost by time if the player to move ran out of time, even while the move is
+    /// checked.
+    /// If the game is paused, then return `[PlayError::GamePaused]`
+    /// If the move is outside the goban return `[PlayError::OutOfBounds]`
+    /// If a player resigns when it's not his turn, returns `[PlayError::WrongColor]`
     /// If the move is a suicide return `[PLayError::SuicideMove]`
     /// If the move is a ko returns `[PlayError::SuicideMove]`Ko
     /// if point is already filled, then return `[PlayError::PointNotEmpty]``
-    /// If the game is paused, then return `[PlayError::GamePaused]`
+    ///
+    /// If the rule says that illegal moves lose, the moves forbidden by the rules
+    /// (see [`PlayError::is_illegal_move`]) end the game with `[EndGame::WinnerByForfeit]` instead.
     pub fn try_play(&mut self, play: Move) -> Result<&mut Self, PlayError> {
-        if self.passes == 2 {
+        match self.validate(play) {
+            // The player can run out of time since the validation
+            Ok(()) if !self.punch_clock() => Err(PlayError::GameOver),
+            Ok(()) => Ok(self.play_after_clock(play)),
+            Err(e) if self.rule.illegal_move_loses && e.is_illegal_move() => {
+                Ok(self.forfeit(self.turn))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the game after the move, the game isn't changed. The new game shares the old
+    /// moves, positions and information with this one, only the goban is copied.
+    ///
+    /// # Panics
+    ///
+    /// Like [`Game::play`].
+    pub fn with_move(&self, play: Move) -> Game {
+        let mut game = self.clone();
+        game.play(play);
+        game
+    }
+
+    /// Returns the game after the move if it's legal, see [`Game::try_play`] and
+    /// [`Game::with_move`].
+    pub fn try_with_move(&self, play: Move) -> Result<Game, PlayError> {
+        let mut game = self.clone();
+        game.try_play(play)?;
+        Ok(game)
+    }
+
+    fn validate(&mut self, play: Move) -> Result<(), PlayError> {
+        self.check_time();
+        if self.outcome.is_some() {
+            Err(PlayError::GameOver)
+        } else if self.passes >= 2 {
             Err(PlayError::GamePaused)
         } else {
             match play {
                 Move::Play(x, y) => {
-                    if self.goban.get_color((x, y)) != EMPTY {
+                    if !is_coord_valid(self.goban.size(), (x, y)) {
+                        Err(PlayError::OutOfBounds)
+                    } else if self.goban.get_color((x, y)) != EMPTY {
                         Err(PlayError::PointNotEmpty)
                     } else if let Some(c) = self.check_point((x as Nat, y as Nat)) {
                         Err(c)
                     } else {
-                        Ok(self.play(play))
+                        Ok(())
                     }
                 }
-                Move::Pass | Move::Resign(_) => Ok(self.play(play)),
+                Move::Resign(player) if player != self.turn => Err(PlayError::WrongColor),
+                Move::Pass | Move::Resign(_) => Ok(()),
             }
         }
     }
 
+    #[inline]
+    pub(crate) fn set_outcome(&mut self, outcome: EndGame) {
+        self.outcome = Some(outcome);
+        self.stop_clock();
+    }
+
+    /// The player loses the game by forfeit, for example after a decision of the arbiter.
+    /// Nothing changes if the game already has an outcome.
+    pub fn forfeit(&mut self, player: Color) -> &mut Self {
+        if self.outcome.is_none() {
+            self.set_outcome(EndGame::WinnerByForfeit(!player));
+        }
+        self
+    }
+
+    /// Adds stones on the goban without playing moves, like the setup properties of a SGF.
+    /// A stone replaces the stone already on his point, nothing is captured.
+    /// Nothing is recorded if there are no stones.
+    ///
+    /// # Panics
+    ///
+    /// If a stone is outside the goban.
+    pub fn add_stones(&mut self, stones: &[Stone]) -> &mut Self {
+        if stones.is_empty() {
+            return self;
+        }
+        self.begin_setup();
+        for &stone in stones {
+            assert!(
+                is_coord_valid(self.goban.size(), stone.coord),
+                "Coordinate {:?} out of bounds",
+                stone.coord
+            );
+            let previous = self.goban.get_color(stone.coord);
+            if previous == Some(stone.color) {
+                continue;
+            }
+            if let Some(color) = previous {
+                self.goban.remove_stone(stone.coord);
+                self.record.setup_mut().erase(Stone {
+                    coord: stone.coord,
+                    color,
+                });
+            }
+            self.goban.push_stone(stone);
+            self.record.setup_mut().add(stone);
+        }
+        self
+    }
+
+    /// Erases stones from the goban without counting them as prisoners.
+    /// Empty points are ignored, nothing is recorded if there are no points.
+    ///
+    /// # Panics
+    ///
+    /// If a point is outside the goban.
+    pub fn erase_stones(&mut self, points: &[Coord]) -> &mut Self {
+        if points.is_empty() {
+            return self;
+        }
+        self.begin_setup();
+        for &coord in points {
+            assert!(
+                is_coord_valid(self.goban.size(), coord),
+                "Coordinate {coord:?} out of bounds"
+            );
+            if let Some(color) = self.goban.remove_stone(coord) {
+                self.record.setup_mut().erase(Stone { coord, color });
+            }
+        }
+        self
+    }
+
+    /// Sets the player to move, like the PL property of a SGF.
+    /// Nothing is recorded if the player already has the turn.
+    pub fn set_turn(&mut self, color: Color) -> &mut Self {
+        if color == self.turn {
+            return self;
+        }
+        self.begin_setup().turn = Some(color);
+        self.turn = color;
+        self
+    }
+
+    /// The position before a setup is kept for the super ko, and the ko point doesn't exist
+    /// anymore.
+    fn begin_setup(&mut self) -> &mut SetupRecord {
+        self.history.insert(&self.goban);
+        self.ko_point = None;
+        self.record.setup_mut()
+    }
+
     /// Put the handicap stones on the goban.
     /// This puts the turn for white but doesn't update the komi.
     pub fn put_handicap(&mut self, points: &[Coord]) {
@@ -315,88 +696,13 @@ impl Game {
         }
     }
 
-    /// Return the number of allied corner and off board corners.
-    fn helper_check_eye(
-        &self,
-        point: (Nat, Nat),
-        color: Color,
-    ) -> (Connections<Coord>, Connections<Coord>, Connections<Coord>) {
-        let mut corner_ally = Connections::new();
-        let mut corner_off_board = Connections::new();
-        let mut empty_corners = Connections::new();
-        for p in corner_points(point) {
-            if is_coord_valid(self.goban.size(), p) {
-                let color_corner = self.goban.get_color(p);
-                match color_corner {
-                    None => {
-                        empty_corners.push(p);
-                    }
-                    Some(c) if c == color => {
-                        corner_ally.push(p);
-                    }
-                    _ => {}
-                }
-            } else {
-                corner_off_board.push(p);
-            }
-        }
-
-        (corner_ally, corner_off_board, empty_corners)
-    }
-
-    /// Detects true eyes. return true is the stone is an eye.
-    /// Except for this form :
-    /// ```{nothing}
-    ///  ++
-    ///  + ++
-    ///  ++ +
-    ///    ++
-    /// ```
-    /// nor handle double-headed dragons.
-    /// This function is only used for performance checking in the rules,
-    /// and not for checking is a point is really an eye!
+    /// Detects real eyes of one point. Returns true if the point is an eye of the color of the
+    /// stone, false eyes are not eyes.
+    /// It's a local check used for the legality of the moves, it doesn't handle two-headed
+    /// dragons. See [`Goban::is_local_eye`], and [`Goban::eye_kind`] for the full analysis.
+    #[inline]
     pub fn check_eye(&self, Stone { coord, color }: Stone) -> bool {
-        // if the intersection is not empty
-        if self.goban.get_color(coord).is_some() {
-            return false;
-        }
-
-        // if he doesn't have the cross then return false
-        if self
-            .goban
-            .get_connected_points(coord)
-            .any(|s| s.color != Some(color))
-        {
-            return false;
-        }
-
-        let (corner_ally, corner_off_board, empty_corners) = self.helper_check_eye(coord, color);
-        let total_corners = corner_ally.len() + corner_off_board.len();
-
-        if total_corners == 4 {
-            return true;
-        }
-
-        // If one corner or 2 are not allied corners, then we need to test, if the remaining corners are an eye.
-        // We cannot call this function recursively because of the complexity with loops.
-        if [3, 2].contains(&total_corners) {
-            for coord in empty_corners {
-                // We test the cross again for the empty corner
-                if self
-                    .goban
-                    .get_connected_stones(coord)
-                    .any(move |s| s.color != color)
-                {
-                    return false;
-                }
-                let (ca, cof, _emc) = self.helper_check_eye(coord, color);
-                let c = ca.len() + cof.len();
-                if c == 3 || c == 2 {
-                    return true;
-                }
-            }
-        }
-        false
+        self.goban.is_local_eye(coord, color)
     }
 
     /// Test if a play is ko.
@@ -414,15 +720,18 @@ impl Game {
     }
 
     /// Rule of the super Ko, if any before configuration was already played, then return true.
+    /// The position after the move is looked up by its hash, the goban is only copied to
+    /// compare the stones in the verified mode.
     pub fn check_super_ko(&self, stone: Stone) -> bool {
         if self.history.len() <= 2 || !self.will_capture(stone.coord) {
             false
+        } else if self.check_ko(stone) {
+            true
         } else {
-            let to_check = self.play_for_verification(stone.coord);
-            let is_it_ko = self.check_ko(stone);
-                is_it_ko ||  self
-                    .history
-                    .contains(&to_check)
+            let (hash, wide_hash) = self.goban.hashes_after(stone);
+            self.history.contains_hashes(hash, wide_hash, || {
+                pack(&self.play_for_verification(stone.coord))
+            })
         }
     }
 
@@ -453,12 +762,41 @@ impl Game {
         println!("{}", self.goban)
     }
 
+    /// Counts the time of the move in the clock, returns false if the player ran out of time.
+    fn punch_clock(&mut self) -> bool {
+        let Some(clock) = &mut self.clock else {
+            return true;
+        };
+        if self.outcome.is_some() || self.passes >= 2 {
+            return true;
+        }
+        if clock.punch(self.turn) {
+            true
+        } else {
+            self.lose_on_time();
+            false
+        }
+    }
+
+    fn lose_on_time(&mut self) {
+        self.outcome = Some(EndGame::WinnerByTime(!self.turn));
+        self.stop_clock();
+    }
+
+    #[inline]
+    fn stop_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.stop(self.turn);
+        }
+    }
+
     #[inline]
     fn remove_captured_stones(&mut self, dead_chains: &[GroupIdx], added_chain: GroupIdx) {
         let ((black_prisoners, white_prisoners), ko_point) = self.goban.remove_captured_stones_aux(
             !self.rule.flag_illegal.contains(IllegalRules::SUICIDE),
             dead_chains,
             added_chain,
+            Some(self.record.captured_mut()),
         );
         self.prisoners.0 += black_prisoners;
         self.prisoners.1 += white_prisoners;
@@ -466,6 +804,15 @@ impl Game {
     }
 }
 
+fn undo_setup(goban: &mut Goban, setup: &SetupRecord) {
+    for stone in &setup.added {
+        goban.remove_stone(stone.coord);
+    }
+    for &stone in &setup.erased {
+        goban.push_stone(stone);
+    }
+}
+
 impl Default for Game {
     fn default() -> Self {
         Game::new(GobanSizes::Nineteen, CHINESE)
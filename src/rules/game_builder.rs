@@ -21,14 +21,16 @@
 use crate::pieces::goban::Goban;
 use crate::pieces::stones::{Color, Stone};
 use crate::pieces::util::coord::{Coord, Size};
+use crate::rules::clock::GameClock;
 use crate::rules::game::Game;
+use crate::rules::game_info::GameInfo;
+use crate::rules::super_ko::SuperKoCheck;
 use crate::rules::{EndGame, Move, Rule, CHINESE};
 use std::mem::take;
 
 pub struct GameBuilder {
     size: Size,
-    black_player: String,
-    white_player: String,
+    info: GameInfo,
     rule: Rule,
     komi: Option<f32>,
     handicap: Option<u32>,
@@ -37,6 +39,8 @@ pub struct GameBuilder {
     moves: Vec<Move>,
     outcome: Option<EndGame>,
     setup: Vec<Stone>,
+    clock: Option<GameClock>,
+    super_ko_check: SuperKoCheck,
 }
 
 impl GameBuilder {
@@ -77,7 +81,12 @@ impl GameBuilder {
     }
 
     pub fn black_player(&mut self, black_player_name: &str) -> &mut Self {
-        self.black_player = black_player_name.to_string();
+        self.info.black_player = Some(black_player_name.to_string());
+        self
+    }
+
+    pub fn black_rank(&mut self, black_rank: &str) -> &mut Self {
+        self.info.black_rank = Some(black_rank.to_string());
         self
     }
 
@@ -87,7 +96,63 @@ impl GameBuilder {
     }
 
     pub fn white_player(&mut self, white_player_name: &str) -> &mut Self {
-        self.white_player = white_player_name.to_string();
+        self.info.white_player = Some(white_player_name.to_string());
+        self
+    }
+
+    pub fn white_rank(&mut self, white_rank: &str) -> &mut Self {
+        self.info.white_rank = Some(white_rank.to_string());
+        self
+    }
+
+    pub fn date(&mut self, date: &str) -> &mut Self {
+        self.info.date = Some(date.to_string());
+        self
+    }
+
+    pub fn event(&mut self, event: &str) -> &mut Self {
+        self.info.event = Some(event.to_string());
+        self
+    }
+
+    pub fn game_name(&mut self, game_name: &str) -> &mut Self {
+        self.info.game_name = Some(game_name.to_string());
+        self
+    }
+
+    pub fn place(&mut self, place: &str) -> &mut Self {
+        self.info.place = Some(place.to_string());
+        self
+    }
+
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.info.comment = Some(comment.to_string());
+        self
+    }
+
+    /// Adds a property unknown by the library, it will be kept in the game info.
+    pub fn unknown_property(&mut self, identifier: &str, value: &str) -> &mut Self {
+        self.info
+            .unknown_properties
+            .push((identifier.to_string(), value.to_string()));
+        self
+    }
+
+    /// Overrides all the information about the game.
+    pub fn info(&mut self, info: GameInfo) -> &mut Self {
+        self.info = info;
+        self
+    }
+
+    /// The clock starts after the moves are played.
+    pub fn clock(&mut self, clock: GameClock) -> &mut Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// How the positions are compared for the super ko, see [`Game::set_super_ko_check`].
+    pub fn super_ko_check(&mut self, check: SuperKoCheck) -> &mut Self {
+        self.super_ko_check = check;
         self
     }
 
@@ -132,13 +197,21 @@ impl GameBuilder {
             handicap,
             history: Default::default(),
             ko_point: None,
+            record: Default::default(),
+            clock: None,
+            info: self.info.into(),
         };
+        g.set_super_ko_check(self.super_ko_check);
 
         // Moves to play
         for &m in &self.moves {
             g.play(m);
         }
 
+        if let Some(clock) = self.clock {
+            g.set_clock(clock);
+        }
+
         Ok(g)
     }
 
@@ -153,8 +226,7 @@ impl Default for GameBuilder {
     fn default() -> Self {
         GameBuilder {
             size: (19, 19),
-            black_player: "".to_string(),
-            white_player: "".to_string(),
+            info: GameInfo::default(),
             handicap_points: vec![],
             rule: CHINESE,
             komi: None,
@@ -163,6 +235,8 @@ impl Default for GameBuilder {
             outcome: None,
             setup: vec![],
             handicap: None,
+            clock: None,
+            super_ko_check: SuperKoCheck::default(),
         }
     }
 }
@@ -0,0 +1,18 @@
+//! Module with the information about a game: players, event, date...
+
+/// Information about a game, it doesn't change how the game is played.
+/// The fields follow the game info properties of the SGF format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameInfo {
+    pub black_player: Option<String>,
+    pub white_player: Option<String>,
+    pub black_rank: Option<String>,
+    pub white_rank: Option<String>,
+    pub date: Option<String>,
+    pub event: Option<String>,
+    pub game_name: Option<String>,
+    pub place: Option<String>,
+    pub comment: Option<String>,
+    /// Root properties not handled by the library, as (identifier, value) pairs.
+    pub unknown_properties: Vec<(String, String)>,
+}
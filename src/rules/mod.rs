@@ -6,8 +6,15 @@ use crate::pieces::stones::Color;
 use crate::pieces::util::coord::{Coord, Size};
 use crate::pieces::Nat;
 
+pub mod clock;
+pub mod features;
 pub mod game;
-mod sgf_bridge;
+pub mod game_info;
+pub mod policy;
+pub mod record;
+pub mod sgf_bridge;
+mod shared;
+pub mod super_ko;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum GobanSizes {
@@ -43,6 +50,7 @@ impl From<usize> for GobanSizes {
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Move {
     Pass,
+    /// The player of this color resigns.
     Resign(Color),
     Play(Nat, Nat),
 }
@@ -83,6 +91,24 @@ pub enum PlayError {
     GamePaused,
     FillEye,
     PointNotEmpty,
+    /// The coordinates are outside the goban.
+    OutOfBounds,
+    /// The game has an outcome (resignation, time...), no more moves can be played.
+    GameOver,
+    /// The move is done in the name of the player who doesn't have the turn.
+    WrongColor,
+}
+
+impl PlayError {
+    /// True if the error is a move forbidden by the rules, the other errors come from moves
+    /// that cannot be played at all.
+    #[inline]
+    pub const fn is_illegal_move(self) -> bool {
+        matches!(
+            self,
+            PlayError::Ko | PlayError::Suicide | PlayError::FillEye | PlayError::PointNotEmpty
+        )
+    }
 }
 
 type FlagUInt = u32;
@@ -116,6 +142,9 @@ pub struct Rule {
     pub komi: f32,
     pub flag_illegal: IllegalRules,
     pub flag_score: ScoreRules,
+    /// If true, a player trying an illegal move loses the game by forfeit instead of getting an
+    /// error.
+    pub illegal_move_loses: bool,
 }
 
 pub static JAPANESE: Rule = Rule {
@@ -126,6 +155,7 @@ pub static JAPANESE: Rule = Rule {
     flag_score: ScoreRules::from_bits_truncate(
         ScoreRules::KOMI.bits() | ScoreRules::PRISONNERS.bits(),
     ),
+    illegal_move_loses: false,
 };
 
 pub static CHINESE: Rule = Rule {
@@ -134,6 +164,7 @@ pub static CHINESE: Rule = Rule {
         IllegalRules::KO.bits() | IllegalRules::SUPERKO.bits() | IllegalRules::SUICIDE.bits(),
     ),
     flag_score: ScoreRules::from_bits_truncate(ScoreRules::KOMI.bits() | ScoreRules::STONES.bits()),
+    illegal_move_loses: false,
 };
 
 impl FromStr for Rule {
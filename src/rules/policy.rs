@@ -0,0 +1,230 @@
+//! Module for the policy of neural networks: a dense index for every move and the mask of the
+//! legal moves in the same order.
+//!
+//! The points are in row major order, `line * width + column`, then the pass is the last index.
+//! A goban of size 19 has 362 indexes.
+
+use crate::pieces::util::coord::{is_coord_valid, Size};
+use crate::pieces::Nat;
+use crate::rules::features::FeatureValue;
+use crate::rules::game::Game;
+use crate::rules::{IllegalRules, Move};
+
+const WORDS: usize = 6;
+
+/// Maximum number of indexes in a policy: 19x19 points and the pass.
+pub const MAX_POLICY_LEN: usize = 19 * 19 + 1;
+
+/// Number of indexes of the policy for a goban of this size.
+#[inline]
+pub const fn policy_len((height, width): Size) -> usize {
+    height as usize * width as usize + 1
+}
+
+/// Index of the move in the policy, None for resign or a point outside the goban.
+#[inline]
+pub const fn move_to_index(play: Move, size: Size) -> Option<usize> {
+    match play {
+        Move::Play(line, column) if is_coord_valid(size, (line, column)) => {
+            Some(line as usize * size.1 as usize + column as usize)
+        }
+        Move::Pass => Some(policy_len(size) - 1),
+        Move::Play(..) | Move::Resign(_) => None,
+    }
+}
+
+/// Move of the index in the policy, None if the index is too big.
+#[inline]
+pub const fn index_to_move(index: usize, size: Size) -> Option<Move> {
+    let width = size.1 as usize;
+    let pass = policy_len(size) - 1;
+    if index < pass {
+        Some(Move::Play((index / width) as Nat, (index % width) as Nat))
+    } else if index == pass {
+        Some(Move::Pass)
+    } else {
+        None
+    }
+}
+
+/// Set of legal moves indexed like the policy, it doesn't allocate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LegalMask {
+    bits: [u64; WORDS],
+    len: u16,
+}
+
+impl LegalMask {
+    /// Empty mask for a goban of this size.
+    pub const fn new(size: Size) -> Self {
+        LegalMask {
+            bits: [0; WORDS],
+            len: policy_len(size) as u16,
+        }
+    }
+
+    /// Number of indexes of the mask, legal or not.
+    #[inline]
+    pub const fn policy_len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// True if no moves are legal.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&w| w == 0)
+    }
+
+    #[inline]
+    pub fn insert(&mut self, index: usize) {
+        debug_assert!(index < self.policy_len(), "index {index} out of the mask");
+        self.bits[index / 64] |= 1 << (index % 64);
+    }
+
+    #[inline]
+    pub fn remove(&mut self, index: usize) {
+        self.bits[index / 64] &= !(1 << (index % 64));
+    }
+
+    #[inline]
+    pub const fn contains(&self, index: usize) -> bool {
+        index < self.len as usize && self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Number of legal moves.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// The bits of the mask, the index `i` is the bit `i % 64` of the word `i / 64`.
+    #[inline]
+    pub const fn as_bits(&self) -> &[u64; WORDS] {
+        &self.bits
+    }
+
+    /// Iterates over the legal indexes in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(word_idx, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    None
+                } else {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    Some(word_idx * 64 + bit)
+                }
+            })
+        })
+    }
+
+    /// Writes the mask in a buffer of [`LegalMask::policy_len`] values, 1 for the legal moves.
+    ///
+    /// # Panics
+    ///
+    /// If the buffer doesn't have the length of the mask.
+    pub fn fill<T: FeatureValue>(&self, buffer: &mut [T]) {
+        assert_eq!(
+            buffer.len(),
+            self.policy_len(),
+            "The buffer doesn't have the size of the mask"
+        );
+        for (index, value) in buffer.iter_mut().enumerate() {
+            *value = if self.contains(index) {
+                T::ONE
+            } else {
+                T::ZERO
+            };
+        }
+    }
+}
+
+impl Game {
+    /// Number of indexes of the policy for the goban of the game.
+    #[inline]
+    pub fn policy_len(&self) -> usize {
+        policy_len(self.size())
+    }
+
+    /// Index of the move in the policy. See [`move_to_index`].
+    #[inline]
+    pub fn move_to_policy(&self, play: Move) -> Option<usize> {
+        move_to_index(play, self.size())
+    }
+
+    /// Move of the index in the policy. See [`index_to_move`].
+    #[inline]
+    pub fn policy_to_move(&self, index: usize) -> Option<Move> {
+        index_to_move(index, self.size())
+    }
+
+    /// Legal moves of the player to move with the pass, following the rule of the game.
+    /// The mask is empty when the game is over.
+    #[inline]
+    pub fn legal_mask(&self) -> LegalMask {
+        self.legal_mask_by(self.rule().flag_illegal)
+    }
+
+    /// Legal moves with the pass, following the rules given in parameter.
+    pub fn legal_mask_by(&self, illegal_rules: IllegalRules) -> LegalMask {
+        let size = self.size();
+        let mut mask = LegalMask::new(size);
+        if self.is_over() {
+            return mask;
+        }
+        for coord in self.pseudo_legals() {
+            if self.check_point_by(coord, illegal_rules).is_none() {
+                mask.insert(coord.0 as usize * size.1 as usize + coord.1 as usize);
+            }
+        }
+        mask.insert(policy_len(size) - 1);
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{GobanSizes, CHINESE};
+
+    #[test]
+    fn indexes() {
+        let size = (19, 19);
+        assert_eq!(move_to_index(Move::Play(0, 0), size), Some(0));
+        assert_eq!(move_to_index(Move::Play(1, 2), size), Some(21));
+        assert_eq!(move_to_index(Move::Pass, size), Some(361));
+        assert_eq!(move_to_index(Move::Play(19, 0), size), None);
+        for index in 0..policy_len(size) {
+            let play = index_to_move(index, size).unwrap();
+            assert_eq!(move_to_index(play, size), Some(index));
+        }
+        assert_eq!(index_to_move(362, size), None);
+    }
+
+    #[test]
+    fn legal_mask() {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.play(Move::Play(0, 1))
+            .play(Move::Pass)
+            .play(Move::Play(1, 0));
+        let mask = game.legal_mask();
+        assert_eq!(mask.policy_len(), 82);
+        // (0,0) is a suicide for white
+        assert!(!mask.contains(0));
+        assert!(!mask.contains(1));
+        assert!(mask.contains(81));
+        assert_eq!(mask.count(), 82 - 3);
+        assert_eq!(mask.iter().count(), mask.count());
+        let legals: Vec<usize> = game
+            .legals()
+            .map(|c| game.move_to_policy(c.into()).unwrap())
+            .collect();
+        assert_eq!(mask.iter().filter(|&i| i != 81).collect::<Vec<_>>(), legals);
+
+        let mut buffer = vec![0f32; mask.policy_len()];
+        mask.fill(&mut buffer);
+        assert_eq!(buffer[0], 0.);
+        assert_eq!(buffer[81], 1.);
+    }
+}
@@ -0,0 +1,164 @@
+//! Module with the record of the moves played in a game.
+
+use std::sync::Arc;
+
+use crate::pieces::stones::{Color, Stone};
+use crate::pieces::util::coord::Coord;
+use crate::rules::shared::SharedVec;
+use crate::rules::Move;
+
+/// A move played in a game, with what it changed on the goban.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveRecord {
+    /// Number of the move, the first move of the game is 1.
+    pub number: u32,
+    /// Color of the player who made the move.
+    pub color: Color,
+    pub play: Move,
+    /// Ko point created by the move, if any.
+    pub ko_point: Option<Coord>,
+    /// Zobrist hash of the goban after the move.
+    pub hash: u64,
+    captures_start: u32,
+    captures_len: u32,
+}
+
+impl MoveRecord {
+    /// Number of stones removed from the goban by this move.
+    #[inline]
+    pub fn number_of_captures(&self) -> usize {
+        self.captures_len as usize
+    }
+}
+
+/// Stones added or erased between two moves, like the setup properties (AB, AW, AE, PL) of a SGF.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetupRecord {
+    /// Number of moves played before the setup.
+    pub after_move: u32,
+    pub added: Vec<Stone>,
+    /// The erased stones with the color they had.
+    pub erased: Vec<Stone>,
+    /// Player to move after the setup, if it was set.
+    pub turn: Option<Color>,
+}
+
+impl SetupRecord {
+    pub(crate) fn add(&mut self, stone: Stone) {
+        self.added.push(stone);
+    }
+
+    /// A stone added by the same setup is forgotten, so a point is only once in each list
+    /// and the erased stones are the ones before the setup.
+    pub(crate) fn erase(&mut self, stone: Stone) {
+        if let Some(pos) = self.added.iter().position(|s| s.coord == stone.coord) {
+            self.added.remove(pos);
+        } else {
+            self.erased.push(stone);
+        }
+    }
+}
+
+/// Ordered list of the moves of a game.
+/// The captured stones of all the moves are stored in one buffer, so recording a move doesn't
+/// allocate. The old moves are shared by the copies of the log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MoveLog {
+    moves: SharedVec<MoveRecord>,
+    captured: SharedVec<Stone>,
+    setups: Arc<Vec<SetupRecord>>,
+}
+
+impl MoveLog {
+    /// Buffer where the stones captured by the next move are pushed before recording it.
+    #[inline]
+    pub(crate) fn captured_mut(&mut self) -> &mut Vec<Stone> {
+        self.captured.tail_mut()
+    }
+
+    /// Records a move, the captured stones are the ones pushed in [`MoveLog::captured_mut`]
+    /// since the last move recorded.
+    pub(crate) fn push(
+        &mut self,
+        color: Color,
+        play: Move,
+        ko_point: Option<Coord>,
+        hash: u64,
+    ) -> &MoveRecord {
+        let captures_start = self
+            .moves
+            .last()
+            .map_or(0, |r| r.captures_start + r.captures_len);
+        self.moves.push(MoveRecord {
+            number: self.moves.len() as u32 + 1,
+            color,
+            play,
+            ko_point,
+            hash,
+            captures_start,
+            captures_len: self.captured.len() as u32 - captures_start,
+        });
+        // The moves and their captures are frozen together, so the captures of a move are in
+        // one chunk.
+        if self.moves.is_full() {
+            self.moves.freeze();
+            self.captured.freeze();
+        }
+        self.moves.last().unwrap()
+    }
+
+    /// Setup after the last move, it's created if there is none.
+    pub(crate) fn setup_mut(&mut self) -> &mut SetupRecord {
+        let after_move = self.moves.len() as u32;
+        let setups = Arc::make_mut(&mut self.setups);
+        if setups.last().is_none_or(|s| s.after_move != after_move) {
+            setups.push(SetupRecord {
+                after_move,
+                ..Default::default()
+            });
+        }
+        setups.last_mut().unwrap()
+    }
+
+    /// Number of moves recorded.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.moves.len() == 0
+    }
+
+    /// Get the move by his number, the first move is 1.
+    pub fn get(&self, number: u32) -> Option<&MoveRecord> {
+        number
+            .checked_sub(1)
+            .and_then(|idx| self.moves.get(idx as usize))
+    }
+
+    #[inline]
+    pub fn last(&self) -> Option<&MoveRecord> {
+        self.moves.last()
+    }
+
+    /// Iterates over the moves from the first to the last.
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &MoveRecord> + ExactSizeIterator {
+        self.moves.iter()
+    }
+
+    /// Setups made during the game, ordered by the number of moves played before them.
+    #[inline]
+    pub fn setups(&self) -> &[SetupRecord] {
+        &self.setups
+    }
+
+    /// Returns the stones removed by the move.
+    /// The record has to come from this log.
+    pub fn captures(&self, record: &MoveRecord) -> &[Stone] {
+        self.captured
+            .slice(record.captures_start as usize, record.captures_len as usize)
+    }
+}
@@ -1,25 +1,104 @@
+//! Module for importing and exporting games in the SGF format.
+
 use sgf_parser::{Action, Outcome, RuleSet, SgfToken};
+use std::fmt::{Display, Formatter};
 
 use crate::pieces::stones::{Color, Stone};
+use crate::pieces::util::coord::{is_coord_valid, Coord};
 use crate::pieces::Nat;
-use crate::rules::game::Game;
 use crate::rules::game::game_builder::GameBuilder;
-use crate::rules::{EndGame, Move, Rule, CHINESE, JAPANESE};
+use crate::rules::game::Game;
+use crate::rules::record::SetupRecord;
+use crate::rules::{EndGame, Move, PlayError, Rule, CHINESE, JAPANESE};
 
 type SgfColor = sgf_parser::Color;
 
+/// How the moves of a SGF are replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SgfReplay {
+    /// The moves are played without checking if they are legal.
+    #[default]
+    Unchecked,
+    /// The import fails on the first illegal move or move out of turn.
+    Strict,
+    /// The illegal moves are reported, they are played anyway when the goban allows it
+    /// (ko, resumed game), skipped otherwise. The moves out of turn are skipped.
+    Lenient,
+}
+
+/// An illegal move found while replaying a SGF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SgfDiagnostic {
+    /// Index of the node in the main variation, the root node is 0.
+    pub node: usize,
+    /// Number of the move in the SGF, the first move is 1.
+    pub move_number: u32,
+    pub play: Move,
+    pub error: PlayError,
+    /// True if the move wasn't played.
+    pub skipped: bool,
+}
+
+impl Display for SgfDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "illegal move {:?} ({:?}) at node {}, move {}",
+            self.play, self.error, self.node, self.move_number
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SgfError {
+    /// The SGF cannot be parsed.
+    Parse(String),
+    /// The game described by the SGF cannot be created.
+    InvalidGame(String),
+    /// An illegal move was found with [`SgfReplay::Strict`].
+    IllegalMove(SgfDiagnostic),
+}
+
+impl Display for SgfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SgfError::Parse(e) => write!(f, "{e}"),
+            SgfError::InvalidGame(e) => write!(f, "invalid game: {e}"),
+            SgfError::IllegalMove(diagnostic) => write!(f, "{diagnostic}"),
+        }
+    }
+}
+
+impl std::error::Error for SgfError {}
+
+/// A game imported from a SGF, with the illegal moves found with [`SgfReplay::Lenient`].
+#[derive(Debug, Clone)]
+pub struct SgfImport {
+    pub game: Game,
+    pub warnings: Vec<SgfDiagnostic>,
+}
+
 impl Game {
+    /// Creates a game from a SGF, the moves are played without checking if they are legal.
     pub fn from_sgf(sgf_str: &str) -> Result<Self, String> {
-        let game_tree = match sgf_parser::parse(sgf_str) {
-            Ok(game) => Ok(game),
-            Err(e) => Err(e.to_string()),
-        }?;
+        Self::from_sgf_with(sgf_str, SgfReplay::Unchecked)
+            .map(|import| import.game)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Creates a game from a SGF, the moves are replayed following the `replay` mode.
+    ///
+    /// # Errors
+    ///
+    /// If the SGF is not valid, or with [`SgfReplay::Strict`] if a move is illegal.
+    pub fn from_sgf_with(sgf_str: &str, replay: SgfReplay) -> Result<SgfImport, SgfError> {
+        let game_tree = sgf_parser::parse(sgf_str).map_err(|e| SgfError::Parse(e.to_string()))?;
         let mut game_builder: GameBuilder = Default::default();
-        let mut first = true;
-        let mut moves = vec![];
+        let mut outcome = None;
+        let mut actions = vec![];
 
-        for node in game_tree.iter() {
-            if first {
+        for (node_idx, node) in game_tree.iter().enumerate() {
+            if node_idx == 0 {
                 // first token if the root token
                 // Game information
                 for token in &node.tokens {
@@ -27,11 +106,17 @@ impl Game {
                         SgfToken::Komi(komi) => {
                             game_builder.komi(*komi);
                         }
-                        SgfToken::Size(x, y) => {
-                            game_builder.size((*x as u8, *y as u8));
+                        // SZ[columns:rows]
+                        SgfToken::Size(width, height) => {
+                            if !(1..=19).contains(width) || !(1..=19).contains(height) {
+                                return Err(SgfError::InvalidGame(format!(
+                                    "The size {width}x{height} is not supported"
+                                )));
+                            }
+                            game_builder.size((*height as u8, *width as u8));
                         }
                         SgfToken::Result(o) => {
-                            game_builder.outcome((*o).into());
+                            outcome = Some((*o).into());
                         }
                         SgfToken::Add {
                             color,
@@ -45,45 +130,555 @@ impl Game {
                                 },
                             });
                         }
-                        SgfToken::Rule(rule) => {
-                            game_builder.rule(rule.clone().into());
-                        }
+                        SgfToken::Rule(rule) => match rule {
+                            RuleSet::Japanese | RuleSet::Chinese => {
+                                game_builder.rule(rule.clone().into());
+                            }
+                            _ => {
+                                return Err(SgfError::InvalidGame(format!(
+                                    "The rule {} is not implemented yet !",
+                                    rule.to_string()
+                                )))
+                            }
+                        },
                         SgfToken::Handicap(handicap) => {
                             game_builder.handicap(*handicap);
                         }
-                        SgfToken::Game(go) => {
-                            assert_eq!(*go, sgf_parser::Game::Go);
+                        SgfToken::Game(go) if *go != sgf_parser::Game::Go => {
+                            return Err(SgfError::InvalidGame(
+                                "Only the game of go is supported".to_string(),
+                            ));
+                        }
+                        SgfToken::PlayerName { color, name } => match color {
+                            SgfColor::Black => {
+                                game_builder.black_player(&unescape(name));
+                            }
+                            SgfColor::White => {
+                                game_builder.white_player(&unescape(name));
+                            }
+                        },
+                        SgfToken::PlayerRank { color, rank } => match color {
+                            SgfColor::Black => {
+                                game_builder.black_rank(&unescape(rank));
+                            }
+                            SgfColor::White => {
+                                game_builder.white_rank(&unescape(rank));
+                            }
+                        },
+                        SgfToken::Date(date) => {
+                            game_builder.date(&unescape(date));
+                        }
+                        SgfToken::Event(event) => {
+                            game_builder.event(&unescape(event));
+                        }
+                        SgfToken::GameName(name) => {
+                            game_builder.game_name(&unescape(name));
+                        }
+                        SgfToken::Place(place) => {
+                            game_builder.place(&unescape(place));
+                        }
+                        SgfToken::Comment(comment) => {
+                            game_builder.comment(&unescape(comment));
+                        }
+                        // Compressed lists like AB[aa:bb] aren't read by the parser
+                        SgfToken::Invalid((key, value)) if key == "AB" || key == "AW" => {
+                            let color = if key == "AB" {
+                                Color::Black
+                            } else {
+                                Color::White
+                            };
+                            for coord in sgf_points(value) {
+                                game_builder.add(Stone { coord, color });
+                            }
+                        }
+                        SgfToken::Unknown((key, value)) => {
+                            if key.as_str() == "PL" {
+                                if let Some(turn) = sgf_color(value) {
+                                    game_builder.turn(turn);
+                                }
+                            } else {
+                                game_builder.unknown_property(key, &unescape(value));
+                            }
                         }
 
                         //TODO another options
                         _ => (),
                     }
-                    for tokens in node.get_unknown_tokens() {
-                        if let SgfToken::Unknown((key, value)) = tokens {
-                            if key.as_str() == "PL" {
-                                match value.as_str() {
-                                    "B" => {
-                                        game_builder.turn(Color::Black);
-                                    }
-                                    "W" => {
-                                        game_builder.turn(Color::White);
-                                    }
-                                    _ => unreachable!(),
-                                }
-                            }
+                }
+            } else {
+                let mut setup = Setup::default();
+                for token in &node.tokens {
+                    match token {
+                        SgfToken::Add {
+                            color,
+                            coordinate: (x, y),
+                        } => setup.added.push(Stone {
+                            coord: ((*y - 1) as Nat, (*x - 1) as Nat),
+                            color: (*color).into(),
+                        }),
+                        SgfToken::Invalid((key, value)) if key == "AB" || key == "AW" => {
+                            let color = if key == "AB" {
+                                Color::Black
+                            } else {
+                                Color::White
+                            };
+                            setup.added.extend(
+                                sgf_points(value)
+                                    .into_iter()
+                                    .map(|coord| Stone { coord, color }),
+                            );
+                        }
+                        SgfToken::Unknown((key, value)) if key == "AE" => {
+                            setup.erased.extend(sgf_points(value));
                         }
+                        SgfToken::Unknown((key, value)) if key == "PL" => {
+                            setup.turn = sgf_color(value);
+                        }
+                        _ => (),
                     }
                 }
-                first = false;
-            } else if !node.tokens.is_empty() {
-                let token = node.tokens.first().unwrap();
-                if let SgfToken::Move { action, .. } = token {
-                    moves.push((*action).into());
+                if setup != Setup::default() {
+                    actions.push((node_idx, NodeAction::Setup(setup)));
+                }
+                // Only one move by node
+                if let Some(SgfToken::Move { color, action }) = node
+                    .tokens
+                    .iter()
+                    .find(|t| matches!(t, SgfToken::Move { .. }))
+                {
+                    actions.push((
+                        node_idx,
+                        NodeAction::Play((*color).into(), (*action).into()),
+                    ));
+                }
+            }
+        }
+        let mut game = game_builder.build().map_err(SgfError::InvalidGame)?;
+        let warnings = replay_moves(&mut game, &actions, replay)?;
+        // The outcome is set after the moves, a finished game doesn't accept moves.
+        match outcome {
+            // A SGF has no resign move, it's put back in the record from the result
+            Some(EndGame::WinnerByResign(winner)) if !game.is_over() => {
+                game.play(Move::Resign(!winner));
+            }
+            Some(outcome) => game.set_outcome(outcome),
+            None => {}
+        }
+        Ok(SgfImport { game, warnings })
+    }
+
+    /// Reads all the games of a SGF collection, like "(;GM[1]...)(;GM[1]...)".
+    /// Each game has his own result, an invalid game doesn't stop the reading of the others.
+    pub fn from_sgf_collection(
+        sgf_str: &str,
+        replay: SgfReplay,
+    ) -> Vec<Result<SgfImport, SgfError>> {
+        split_collection(sgf_str)
+            .into_iter()
+            .map(|game_tree| game_tree.and_then(|sgf| Self::from_sgf_with(sgf, replay)))
+            .collect()
+    }
+
+    /// Writes the game in the SGF format: the game information, the position at the start of the
+    /// game, then the moves and the setups of the record.
+    pub fn to_sgf(&self) -> String {
+        let mut sgf = String::from("(;GM[1]FF[4]CA[UTF-8]");
+        let (height, width) = self.size();
+        if height == width {
+            sgf += &format!("SZ[{height}]");
+        } else {
+            sgf += &format!("SZ[{width}:{height}]");
+        }
+        sgf += &format!("KM[{}]", self.komi());
+        let rule = self.rule();
+        if rule.flag_score == JAPANESE.flag_score && rule.flag_illegal == JAPANESE.flag_illegal {
+            sgf += "RU[Japanese]";
+        } else if rule.flag_score == CHINESE.flag_score && rule.flag_illegal == CHINESE.flag_illegal
+        {
+            sgf += "RU[Chinese]";
+        }
+        if self.handicap() > 0 {
+            sgf += &format!("HA[{}]", self.handicap());
+        }
+        let info = self.info();
+        let text_properties = [
+            ("PB", &info.black_player),
+            ("BR", &info.black_rank),
+            ("PW", &info.white_player),
+            ("WR", &info.white_rank),
+            ("DT", &info.date),
+            ("EV", &info.event),
+            ("GN", &info.game_name),
+            ("PC", &info.place),
+            ("C", &info.comment),
+        ];
+        for (id, value) in text_properties {
+            if let Some(value) = value {
+                sgf += &format!("{id}[{}]", escape(value));
+            }
+        }
+        for (id, value) in &info.unknown_properties {
+            sgf += &format!("{id}[{}]", escape(value));
+        }
+        if let Some(outcome) = self.outcome() {
+            sgf += &format!("RE[{}]", outcome_to_sgf(outcome));
+        }
+
+        // Position at the start of the game
+        let start = self.goban_at(0);
+        write_points(
+            &mut sgf,
+            "AB",
+            start
+                .get_stones()
+                .filter(|s| s.color == Color::Black)
+                .map(|s| s.coord),
+        );
+        write_points(
+            &mut sgf,
+            "AW",
+            start
+                .get_stones()
+                .filter(|s| s.color == Color::White)
+                .map(|s| s.coord),
+        );
+        let default_turn = if self.handicap() > 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let start_turn = self.record().get(1).map_or(self.turn(), |m| m.color);
+        let setup_turn = self
+            .record()
+            .setups()
+            .first()
+            .is_some_and(|s| s.after_move == 0 && s.turn.is_some());
+        if start_turn != default_turn && !setup_turn {
+            sgf += &format!("PL[{}]", color_to_sgf(start_turn));
+        }
+
+        let mut setups = self.record().setups().iter().peekable();
+        for record in self.record().iter() {
+            while let Some(setup) = setups.next_if(|s| s.after_move < record.number) {
+                write_setup(&mut sgf, setup);
+            }
+            match record.play {
+                Move::Play(x, y) => {
+                    sgf += &format!(";{}[{}]", color_to_sgf(record.color), point_to_sgf((x, y)));
+                }
+                Move::Pass => sgf += &format!(";{}[]", color_to_sgf(record.color)),
+                // The resignation is in the result
+                Move::Resign(_) => {}
+            }
+        }
+        for setup in setups {
+            write_setup(&mut sgf, setup);
+        }
+        sgf.push(')');
+        sgf
+    }
+}
+
+/// Writes the games in one SGF collection.
+pub fn to_sgf_collection<'a>(games: impl IntoIterator<Item = &'a Game>) -> String {
+    games
+        .into_iter()
+        .map(|game| game.to_sgf())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits a collection in the SGF of each game tree, the text between the game trees is ignored.
+fn split_collection(sgf_str: &str) -> Vec<Result<&str, SgfError>> {
+    let mut game_trees = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    let mut in_value = false;
+    let mut escaped = false;
+    for (idx, c) in sgf_str.char_indices() {
+        if in_value {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                ']' => in_value = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '[' if depth > 0 => in_value = true,
+            '(' => {
+                if depth == 0 {
+                    start = idx;
+                }
+                depth += 1;
+            }
+            ')' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    game_trees.push(Ok(&sgf_str[start..=idx]));
                 }
             }
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        game_trees.push(Err(SgfError::Parse(format!(
+            "The game tree starting at {start} is not closed"
+        ))));
+    }
+    game_trees
+}
+
+fn write_setup(sgf: &mut String, setup: &SetupRecord) {
+    sgf.push(';');
+    // A point in the added stones doesn't need to be erased, AB and AW replace the stones.
+    write_points(
+        sgf,
+        "AE",
+        setup
+            .erased
+            .iter()
+            .filter(|e| setup.added.iter().all(|a| a.coord != e.coord))
+            .map(|s| s.coord),
+    );
+    write_points(
+        sgf,
+        "AB",
+        setup
+            .added
+            .iter()
+            .filter(|s| s.color == Color::Black)
+            .map(|s| s.coord),
+    );
+    write_points(
+        sgf,
+        "AW",
+        setup
+            .added
+            .iter()
+            .filter(|s| s.color == Color::White)
+            .map(|s| s.coord),
+    );
+    if let Some(turn) = setup.turn {
+        *sgf += &format!("PL[{}]", color_to_sgf(turn));
+    }
+}
+
+/// Writes a property with a list of points, nothing if the list is empty.
+fn write_points(sgf: &mut String, id: &str, points: impl Iterator<Item = Coord>) {
+    let mut points = points.peekable();
+    if points.peek().is_none() {
+        return;
+    }
+    *sgf += id;
+    for point in points {
+        *sgf += &format!("[{}]", point_to_sgf(point));
+    }
+}
+
+/// Writes a point like "cd", the column comes first.
+fn point_to_sgf((line, column): Coord) -> String {
+    let to_char = |n: Nat| {
+        if n < 26 {
+            (b'a' + n) as char
+        } else {
+            (b'A' + n - 26) as char
+        }
+    };
+    format!("{}{}", to_char(column), to_char(line))
+}
+
+fn color_to_sgf(color: Color) -> &'static str {
+    match color {
+        Color::Black => "B",
+        Color::White => "W",
+    }
+}
+
+fn outcome_to_sgf(outcome: EndGame) -> String {
+    match outcome {
+        EndGame::WinnerByScore(c, points) => format!("{}+{points}", color_to_sgf(c)),
+        EndGame::WinnerByResign(c) => format!("{}+R", color_to_sgf(c)),
+        EndGame::WinnerByTime(c) => format!("{}+T", color_to_sgf(c)),
+        EndGame::WinnerByForfeit(c) => format!("{}+F", color_to_sgf(c)),
+        EndGame::Draw => "Draw".to_string(),
+    }
+}
+
+/// Escapes a text value, "]" and "\" are prefixed by "\".
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == ']' || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The parser keeps the escape characters of the values, they are removed here.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                unescaped.push(next);
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Setup properties of a node (AB, AW, AE, PL).
+#[derive(Debug, Default, PartialEq)]
+struct Setup {
+    added: Vec<Stone>,
+    erased: Vec<Coord>,
+    turn: Option<Color>,
+}
+
+/// What a node of the main variation does to the game.
+#[derive(Debug)]
+enum NodeAction {
+    Setup(Setup),
+    /// A move with the color of the player who played it.
+    Play(Color, Move),
+}
+
+/// Parses a point like "cd", the column comes first.
+fn sgf_point(value: &str) -> Option<Coord> {
+    let to_nat = |c: u8| match c {
+        b'a'..=b'z' => Some((c - b'a') as Nat),
+        b'A'..=b'Z' => Some((c - b'A' + 26) as Nat),
+        _ => None,
+    };
+    match value.as_bytes() {
+        &[x, y] => Some((to_nat(y)?, to_nat(x)?)),
+        _ => None,
+    }
+}
+
+/// Parses a point or a compressed rectangle of points like "aa:cc", from one corner to the
+/// opposite one.
+fn sgf_points(value: &str) -> Vec<Coord> {
+    let Some((first, last)) = value.split_once(':') else {
+        return sgf_point(value).into_iter().collect();
+    };
+    let (Some(first), Some(last)) = (sgf_point(first), sgf_point(last)) else {
+        return vec![];
+    };
+    let columns = first.1.min(last.1)..=first.1.max(last.1);
+    (first.0.min(last.0)..=first.0.max(last.0))
+        .flat_map(|line| columns.clone().map(move |column| (line, column)))
+        .collect()
+}
+
+fn sgf_color(value: &str) -> Option<Color> {
+    match value {
+        "B" => Some(Color::Black),
+        "W" => Some(Color::White),
+        _ => None,
+    }
+}
+
+/// Plays the node actions with their node index, following the replay mode.
+/// The setups are always applied. The first move and the moves after a setup without PL choose
+/// the player to move, the other moves out of turn are only played with
+/// [`SgfReplay::Unchecked`].
+fn replay_moves(
+    game: &mut Game,
+    actions: &[(usize, NodeAction)],
+    replay: SgfReplay,
+) -> Result<Vec<SgfDiagnostic>, SgfError> {
+    let mut warnings = vec![];
+    let mut move_number = 0;
+    let mut any_color = true;
+    for (node, action) in actions {
+        let (node, color, play) = match action {
+            NodeAction::Setup(setup) => {
+                game.erase_stones(&setup.erased).add_stones(&setup.added);
+                if let Some(turn) = setup.turn {
+                    game.set_turn(turn);
+                }
+                any_color = setup.turn.is_none();
+                continue;
+            }
+            &NodeAction::Play(color, play) => (*node, color, play),
+        };
+        move_number += 1;
+        if color != game.turn() {
+            if !any_color && replay != SgfReplay::Unchecked {
+                let diagnostic = SgfDiagnostic {
+                    node,
+                    move_number,
+                    play,
+                    error: PlayError::WrongColor,
+                    skipped: true,
+                };
+                if replay == SgfReplay::Strict {
+                    return Err(SgfError::IllegalMove(diagnostic));
+                }
+                warnings.push(diagnostic);
+                continue;
+            }
+            game.set_turn(color);
+        }
+        any_color = false;
+        if replay == SgfReplay::Unchecked {
+            if let Move::Play(x, y) = play {
+                if !is_coord_valid(game.size(), (x, y)) {
+                    return Err(SgfError::IllegalMove(SgfDiagnostic {
+                        node,
+                        move_number,
+                        play,
+                        error: PlayError::OutOfBounds,
+                        skipped: true,
+                    }));
+                }
+            }
+            game.play(play);
+            continue;
+        }
+        let Err(error) = game.try_play(play) else {
+            continue;
+        };
+        let mut diagnostic = SgfDiagnostic {
+            node,
+            move_number,
+            play,
+            error,
+            skipped: true,
+        };
+        if replay == SgfReplay::Strict {
+            return Err(SgfError::IllegalMove(diagnostic));
+        }
+        diagnostic.skipped = !force_play(game, play, error);
+        warnings.push(diagnostic);
+    }
+    Ok(warnings)
+}
+
+/// Plays a move refused by `try_play` when the goban stays coherent, returns true if it's played.
+fn force_play(game: &mut Game, play: Move, error: PlayError) -> bool {
+    match error {
+        PlayError::Ko | PlayError::FillEye => {
+            game.play(play);
+            true
+        }
+        PlayError::GamePaused => {
+            game.resume();
+            match game.try_play(play) {
+                Ok(_) => true,
+                Err(error) if error != PlayError::GamePaused => force_play(game, play, error),
+                Err(_) => false,
+            }
         }
-        game_builder.moves(&moves);
-        game_builder.build()
+        _ => false,
     }
 }
 
@@ -0,0 +1,339 @@
+//! Module with an append only vector shared between the copies of a game.
+//!
+//! The items are frozen in chunks behind an [`Arc`], so a copy of the vector only copies the
+//! items pushed since the last chunk was frozen. The copies share the frozen chunks.
+//! [`SharedSet`] shares a hash set the same way, in levels merged as they grow.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// Number of items after which the owners of the vectors freeze them.
+const CHUNK_SIZE: usize = 32;
+
+struct Chunk<T> {
+    items: Vec<T>,
+    /// Index of the first item of the chunk in the vector.
+    start: usize,
+    previous: Option<Arc<Chunk<T>>>,
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedVec<T> {
+    frozen: Option<Arc<Chunk<T>>>,
+    tail: Vec<T>,
+}
+
+impl<T> Default for SharedVec<T> {
+    fn default() -> Self {
+        SharedVec {
+            frozen: None,
+            tail: vec![],
+        }
+    }
+}
+
+impl<T> SharedVec<T> {
+    #[inline]
+    fn frozen_len(&self) -> usize {
+        self.frozen
+            .as_ref()
+            .map_or(0, |chunk| chunk.start + chunk.items.len())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.frozen_len() + self.tail.len()
+    }
+
+    /// Items pushed since the last freeze.
+    #[inline]
+    pub fn tail_mut(&mut self) -> &mut Vec<T> {
+        &mut self.tail
+    }
+
+    #[inline]
+    pub fn push(&mut self, item: T) {
+        self.tail.push(item);
+    }
+
+    /// The tail has [`CHUNK_SIZE`] items or more, it's time to freeze it.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.tail.len() >= CHUNK_SIZE
+    }
+
+    /// Puts the items pushed since the last freeze in a chunk shared by the copies.
+    pub fn freeze(&mut self) {
+        if self.tail.is_empty() {
+            return;
+        }
+        let start = self.frozen_len();
+        self.frozen = Some(Arc::new(Chunk {
+            items: std::mem::take(&mut self.tail),
+            start,
+            previous: self.frozen.take(),
+        }));
+    }
+
+    /// Items of the chunks from the last pushed to the first, with the index of their first
+    /// item.
+    pub fn chunks(&self) -> impl Iterator<Item = (usize, &[T])> {
+        let mut frozen = self.frozen.as_deref();
+        std::iter::once((self.frozen_len(), self.tail.as_slice())).chain(std::iter::from_fn(
+            move || {
+                let chunk = frozen?;
+                frozen = chunk.previous.as_deref();
+                Some((chunk.start, chunk.items.as_slice()))
+            },
+        ))
+    }
+
+    /// Chunk with the item, the tail if the index is after the last item.
+    fn chunk_of(&self, idx: usize) -> (usize, &[T]) {
+        self.chunks()
+            .find(|&(start, _)| start <= idx)
+            .unwrap_or((0, &[]))
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        let (start, items) = self.chunk_of(idx);
+        items.get(idx - start)
+    }
+
+    #[inline]
+    pub fn last(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|idx| self.get(idx))
+    }
+
+    /// # Panics
+    /// If the items aren't in the same chunk.
+    pub fn slice(&self, start: usize, len: usize) -> &[T] {
+        let (chunk_start, items) = self.chunk_of(start);
+        &items[start - chunk_start..start - chunk_start + len]
+    }
+
+    /// Iterates over the items from the first pushed to the last.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut chunks: Vec<&[T]> = self.chunks().map(|(_, items)| items).collect();
+        chunks.reverse();
+        Iter {
+            chunks: chunks.into_iter(),
+            front: [].iter(),
+            back: [].iter(),
+            len: self.len(),
+        }
+    }
+}
+
+/// Iterator over the items of a [`SharedVec`], it walks the chunks one after the other.
+pub(crate) struct Iter<'a, T> {
+    /// Chunks not started from the front or the back, from the first to the last.
+    chunks: std::vec::IntoIter<&'a [T]>,
+    front: std::slice::Iter<'a, T>,
+    back: std::slice::Iter<'a, T>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(item) = self.front.next() {
+                self.len -= 1;
+                return Some(item);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.front = chunk.iter(),
+                None => {
+                    let item = self.back.next()?;
+                    self.len -= 1;
+                    return Some(item);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.back.next_back() {
+                self.len -= 1;
+                return Some(item);
+            }
+            match self.chunks.next_back() {
+                Some(chunk) => self.back = chunk.iter(),
+                None => {
+                    let item = self.front.next_back()?;
+                    self.len -= 1;
+                    return Some(item);
+                }
+            }
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+/// Set with the items frozen in levels behind an [`Arc`], shared between the copies like a
+/// [`SharedVec`].
+///
+/// A level is merged with the previous one when it's at least as big, so there are
+/// O(log n) levels to look up and every item is copied O(log n) times.
+#[derive(Clone)]
+pub(crate) struct SharedSet<T> {
+    frozen: Option<Arc<Level<T>>>,
+    tail: HashSet<T>,
+}
+
+struct Level<T> {
+    items: HashSet<T>,
+    previous: Option<Arc<Level<T>>>,
+}
+
+impl<T> Default for SharedSet<T> {
+    fn default() -> Self {
+        SharedSet {
+            frozen: None,
+            tail: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> SharedSet<T> {
+    /// Levels of the set from the last frozen to the first.
+    fn levels(&self) -> impl Iterator<Item = &HashSet<T>> {
+        let mut frozen = self.frozen.as_deref();
+        std::iter::once(&self.tail).chain(std::iter::from_fn(move || {
+            let level = frozen?;
+            frozen = level.previous.as_deref();
+            Some(&level.items)
+        }))
+    }
+
+    #[inline]
+    pub fn contains(&self, item: &T) -> bool {
+        self.levels().any(|items| items.contains(item))
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.levels().all(HashSet::is_empty)
+    }
+
+    /// Returns false if the item was already in the set.
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.contains(&item) || !self.tail.insert(item) {
+            return false;
+        }
+        if self.tail.len() >= CHUNK_SIZE {
+            self.freeze();
+        }
+        true
+    }
+
+    fn freeze(&mut self) {
+        let mut items = std::mem::take(&mut self.tail);
+        while let Some(level) = self.frozen.take() {
+            if level.items.len() > items.len() {
+                self.frozen = Some(level);
+                break;
+            }
+            items.extend(level.items.iter().cloned());
+            self.frozen = level.previous.clone();
+        }
+        self.frozen = Some(Arc::new(Level {
+            items,
+            previous: self.frozen.take(),
+        }));
+    }
+}
+
+impl<T: Debug> Debug for SharedSet<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut frozen = self.frozen.as_deref();
+        let mut set = f.debug_set();
+        set.entries(&self.tail);
+        while let Some(level) = frozen {
+            set.entries(&level.items);
+            frozen = level.previous.as_deref();
+        }
+        set.finish()
+    }
+}
+
+impl<T: Debug> Debug for SharedVec<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SharedVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for SharedVec<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{SharedSet, SharedVec, CHUNK_SIZE};
+
+    #[test]
+    fn shared_vec() {
+        let mut parent = SharedVec::default();
+        parent.push(0);
+        parent.push(1);
+        parent.freeze();
+        parent.push(2);
+        let mut child = parent.clone();
+        child.freeze();
+        child.push(3);
+        assert_eq!(child.len(), 4);
+        assert_eq!(child.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(child.iter().next_back(), Some(&3));
+        assert_eq!(child.get(1), Some(&1));
+        assert_eq!(child.get(4), None);
+        assert_eq!(child.slice(0, 2), &[0, 1]);
+        assert_eq!(child.slice(4, 0), &[] as &[i32]);
+        assert_eq!(child.last(), Some(&3));
+        assert_eq!(parent.len(), 3);
+        assert_ne!(parent, child);
+        parent.push(3);
+        assert_eq!(parent, child);
+
+        let mut iter = child.iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&2));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn shared_set() {
+        let mut parent = SharedSet::default();
+        for i in 0..3 * CHUNK_SIZE {
+            assert!(parent.insert(i));
+        }
+        assert!(!parent.insert(0));
+        assert_eq!(parent.levels().count(), 3);
+        let mut child = parent.clone();
+        assert!(child.insert(3 * CHUNK_SIZE));
+        assert!(child.contains(&(3 * CHUNK_SIZE)));
+        assert!(!parent.contains(&(3 * CHUNK_SIZE)));
+        assert!((0..3 * CHUNK_SIZE).all(|i| child.contains(&i)));
+    }
+}
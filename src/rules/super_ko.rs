@@ -0,0 +1,190 @@
+//! Module with the positions of a game kept for the super ko.
+//!
+//! The positions are stored by their zobrist hash in hash sets, so they only cost a few bytes
+//! each and are found without scanning the game, and the old positions are shared by the
+//! copies of a game. The [`SuperKoCheck::Verified`] mode also keeps the stones of the positions
+//! to tell two positions with the same hash apart.
+
+use crate::pieces::goban::Goban;
+use crate::pieces::stones::Color;
+use crate::rules::shared::SharedSet;
+#[cfg(feature = "history")]
+use crate::rules::shared::SharedVec;
+
+/// How the positions are compared for the super ko.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SuperKoCheck {
+    /// Only the zobrist hashes of the positions are kept, two positions with the same hash
+    /// are the same position.
+    #[default]
+    Hash,
+    /// The stones of the positions are kept too, packed on two bits by point. A position is
+    /// repeated only if it has the same hash and the same stones as a previous one.
+    Verified,
+    /// The 128 bits hashes of [`Goban::wide_hash`] are kept too, a position is repeated if it
+    /// has the same 64 bits and 128 bits hashes as a previous one.
+    WideHash,
+}
+
+/// Positions already seen in a game, shared with the copies of the game.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PositionHistory {
+    check: SuperKoCheck,
+    len: usize,
+    /// Hashes of the positions added in the hash mode.
+    hashes: SharedSet<u64>,
+    /// Hashes of the positions added in the verified and wide hash modes.
+    checked_hashes: SharedSet<u64>,
+    /// Packed stones of the positions with their hash, only in the verified mode.
+    positions: SharedSet<(u64, Box<[u8]>)>,
+    /// 128 bits hashes of the positions, only in the wide hash mode.
+    wide_hashes: SharedSet<u128>,
+    #[cfg(feature = "history")]
+    gobans: SharedVec<Goban>,
+}
+
+impl PositionHistory {
+    #[inline]
+    pub fn check(&self) -> SuperKoCheck {
+        self.check
+    }
+
+    /// The positions are compared in the mode they were added with.
+    #[inline]
+    pub fn set_check(&mut self, check: SuperKoCheck) {
+        self.check = check;
+    }
+
+    /// Number of different positions.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// # Panics
+    /// In the wide hash mode, if the goban has no wide keys.
+    pub fn insert(&mut self, goban: &Goban) {
+        if self.contains(goban) {
+            return;
+        }
+        self.len += 1;
+        let hash = goban.zobrist_hash();
+        match self.check {
+            SuperKoCheck::Hash => {
+                self.hashes.insert(hash);
+            }
+            SuperKoCheck::Verified => {
+                self.checked_hashes.insert(hash);
+                self.positions.insert((hash, pack(goban)));
+            }
+            SuperKoCheck::WideHash => {
+                let wide_hash = goban.wide_hash().expect("The goban has no wide keys");
+                self.checked_hashes.insert(hash);
+                self.wide_hashes.insert(wide_hash);
+            }
+        }
+        #[cfg(feature = "history")]
+        {
+            self.gobans.push(goban.clone());
+            if self.gobans.is_full() {
+                self.gobans.freeze();
+            }
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, goban: &Goban) -> bool {
+        self.contains_hashes(goban.zobrist_hash(), goban.wide_hash(), || pack(goban))
+    }
+
+    /// Looks for the position with its hashes, the stones are only packed when a verified
+    /// position has the same hash.
+    pub fn contains_hashes(
+        &self,
+        hash: u64,
+        wide_hash: Option<u128>,
+        stones: impl FnOnce() -> Box<[u8]>,
+    ) -> bool {
+        if self.hashes.contains(&hash) {
+            return true;
+        }
+        if !self.checked_hashes.contains(&hash) {
+            return false;
+        }
+        wide_hash.is_some_and(|wide_hash| self.wide_hashes.contains(&wide_hash))
+            || !self.positions.is_empty() && self.positions.contains(&(hash, stones()))
+    }
+
+    #[cfg(feature = "history")]
+    #[inline]
+    pub fn gobans(&self) -> impl Iterator<Item = &Goban> {
+        self.gobans.iter()
+    }
+}
+
+/// Stones of the goban on two bits by point, in the order of the board.
+pub(crate) fn pack(goban: &Goban) -> Box<[u8]> {
+    let (height, width) = goban.size();
+    let mut packed = vec![0u8; (height as usize * width as usize).div_ceil(4)];
+    for stone in goban.get_stones() {
+        let idx = stone.coord.0 as usize * width as usize + stone.coord.1 as usize;
+        let bits = match stone.color {
+            Color::Black => 1,
+            Color::White => 2,
+        };
+        packed[idx / 4] |= bits << (2 * (idx % 4));
+    }
+    packed.into_boxed_slice()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pieces::zobrist::ZobristKeys;
+    use std::sync::Arc;
+
+    #[test]
+    fn position_history() {
+        let mut goban = Goban::new((9, 9));
+        let mut history = PositionHistory::default();
+        history.set_check(SuperKoCheck::Verified);
+        history.insert(&goban);
+        goban.push((4, 4), Color::Black);
+        assert!(!history.contains(&goban));
+        history.insert(&goban);
+        assert!(history.contains(&goban));
+        goban.remove_stone((4, 4));
+        assert!(history.contains(&goban));
+        assert_eq!(history.len(), 2);
+
+        goban.push((4, 4), Color::White);
+        assert!(!history.contains(&goban));
+        assert_eq!(pack(&goban).len(), 21);
+        assert_eq!(pack(&goban)[10], 2);
+    }
+
+    #[test]
+    fn wide_hash_history() {
+        let mut goban = Goban::new((9, 13));
+        goban.set_wide_keys(Arc::new(ZobristKeys::new((9, 13))));
+        let mut history = PositionHistory::default();
+        history.insert(&goban);
+        history.set_check(SuperKoCheck::WideHash);
+        goban.push((4, 12), Color::Black);
+        assert!(!history.contains(&goban));
+        history.insert(&goban);
+        assert!(history.contains(&goban));
+        // The first position is still compared by hash
+        goban.remove_stone((4, 12));
+        assert!(history.contains(&goban));
+        assert_eq!(history.len(), 2);
+
+        // Same 64 bits hash but another 128 bits hash
+        let (hash, wide_hash) = (goban.zobrist_hash(), goban.wide_hash().unwrap());
+        goban.push((4, 12), Color::Black);
+        let stones = || unreachable!("Only verified positions are packed");
+        assert!(history.contains_hashes(goban.zobrist_hash(), goban.wide_hash(), stones));
+        assert!(!history.contains_hashes(goban.zobrist_hash(), Some(wide_hash), stones));
+        assert!(history.contains_hashes(hash, None, stones));
+    }
+}
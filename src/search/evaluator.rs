@@ -0,0 +1,135 @@
+//! Module with the evaluators of the leaves of the search tree.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::pieces::stones::{Color, Stone};
+use crate::rules::game::Game;
+use crate::rules::Move;
+
+/// Result of the evaluation of a position.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Evaluation {
+    /// Probability of winning for the player to move, between 0 and 1.
+    pub value: f32,
+    /// Moves played by the evaluator after the position, used for RAVE.
+    /// Empty if the evaluator doesn't play.
+    pub played: Vec<(Color, Move)>,
+    /// Prior probabilities of the moves of the position, a policy for example.
+    /// The moves without a prior get 0.
+    pub priors: Vec<(Move, f32)>,
+}
+
+/// Evaluates the positions of the leaves of the search tree, with random playouts or with an
+/// external model like a neural network.
+pub trait Evaluator {
+    /// Evaluates the position, the game isn't over.
+    fn evaluate(&mut self, game: &Game) -> Evaluation;
+
+    /// Evaluator for another thread of a parallel search, a copy by default.
+    fn fork(&mut self) -> Self
+    where
+        Self: Sized + Clone,
+    {
+        self.clone()
+    }
+}
+
+/// Plays random moves until the end of the game, without filling the real eyes.
+#[derive(Debug, Clone)]
+pub struct RandomPlayout {
+    rng: StdRng,
+    /// Maximum number of moves of a playout by point of the goban, the playout is scored as it
+    /// is after that.
+    pub moves_by_point: usize,
+}
+
+impl RandomPlayout {
+    pub fn new() -> Self {
+        Self::from_rng(StdRng::from_os_rng())
+    }
+
+    /// Playouts always playing the same moves, for tests and benchmarks.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::from_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng(rng: StdRng) -> Self {
+        RandomPlayout {
+            rng,
+            moves_by_point: 3,
+        }
+    }
+
+    fn random_move(&mut self, game: &Game) -> Move {
+        let mut points = game.pseudo_legals_vec();
+        points.shuffle(&mut self.rng);
+        points
+            .into_iter()
+            .find(|&point| {
+                game.check_point(point).is_none()
+                    && !game.check_eye(Stone {
+                        coord: point,
+                        color: game.turn(),
+                    })
+            })
+            .map_or(Move::Pass, Move::from)
+    }
+}
+
+impl Default for RandomPlayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator for RandomPlayout {
+    fn evaluate(&mut self, game: &Game) -> Evaluation {
+        let turn = game.turn();
+        let (height, width) = game.size();
+        let max_moves = self.moves_by_point * height as usize * width as usize;
+        let mut state = game.clone();
+        let mut played = Vec::with_capacity(max_moves);
+        while !state.is_over() && played.len() < max_moves {
+            let play = self.random_move(&state);
+            played.push((state.turn(), play));
+            state.play(play);
+        }
+        Evaluation {
+            value: value_for(&state, turn),
+            played,
+            priors: vec![],
+        }
+    }
+
+    /// The copy plays other moves, its seed is drawn from these playouts.
+    fn fork(&mut self) -> Self {
+        RandomPlayout {
+            rng: StdRng::from_rng(&mut self.rng),
+            moves_by_point: self.moves_by_point,
+        }
+    }
+}
+
+/// 1 if the player wins the game, 0.5 for a draw, the game is scored if it isn't over.
+pub(crate) fn value_for(game: &Game, player: Color) -> f32 {
+    let winner = match game.outcome() {
+        Some(outcome) => outcome.get_winner(),
+        None => {
+            let (black, white) = game.calculate_score();
+            if (black - white).abs() < f32::EPSILON {
+                None
+            } else if black > white {
+                Some(Color::Black)
+            } else {
+                Some(Color::White)
+            }
+        }
+    };
+    match winner {
+        Some(winner) if winner == player => 1.,
+        Some(_) => 0.,
+        None => 0.5,
+    }
+}
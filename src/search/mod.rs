@@ -0,0 +1,553 @@
+//! Module with a Monte Carlo tree search engine, enabled by the `search` feature.
+//!
+//! The engine is an UCT search with RAVE (all moves as first), the leaves are evaluated by an
+//! [`Evaluator`], random playouts by default or an external model. The tree is kept between
+//! the moves of the game with [`Search::advance`].
+//!
+//! # Example
+//! ```
+//! use goban::rules::game::Game;
+//! use goban::rules::{GobanSizes, CHINESE};
+//! use goban::search::{Budget, RandomPlayout, Search, SearchConfig};
+//!
+//! let game = Game::new(GobanSizes::Custom(5, 5), CHINESE);
+//! let mut search = Search::new(game, RandomPlayout::with_seed(42), SearchConfig::default());
+//! let result = search.run(Budget::Playouts(100));
+//! let best_move = result.best_move.unwrap();
+//! assert_eq!(result.principal_variation[0], best_move);
+//! assert!((0. ..=1.).contains(&result.win_rate));
+//!
+//! // The subtree of the move is kept for the next search
+//! search.advance(best_move);
+//! assert!(search.playouts() > 0);
+//! ```
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::pieces::stones::Color;
+use crate::rules::game::Game;
+use crate::rules::policy::{move_to_index, policy_len};
+use crate::rules::{IllegalRules, Move};
+
+use self::evaluator::value_for;
+pub use self::evaluator::{Evaluation, Evaluator, RandomPlayout};
+pub use self::parallel::evaluate_parallel;
+
+pub mod evaluator;
+pub mod parallel;
+
+const ROOT: usize = 0;
+
+/// Parameters of the search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchConfig {
+    /// Weight of the exploration term of UCT.
+    pub exploration: f32,
+    /// Number of visits of a move at which its RAVE value and its own value weigh the same.
+    /// 0 disables RAVE.
+    pub rave_equivalence: f32,
+    /// Value of the moves never visited and never seen in a playout.
+    pub first_play_urgency: f32,
+    /// Value of the pass before its first visit, low so the moves on the goban are tried first.
+    pub pass_urgency: f32,
+    /// Weight of the priors given by the evaluator, decreasing with the visits of the move.
+    pub prior_weight: f32,
+}
+
+impl Default for SearchConfig {
+    /// Values suited to random playouts, the exploration is low because RAVE already spreads
+    /// the visits.
+    fn default() -> Self {
+        SearchConfig {
+            exploration: 0.25,
+            rave_equivalence: 1000.,
+            first_play_urgency: 1.1,
+            pass_urgency: 0.,
+            prior_weight: 1.,
+        }
+    }
+}
+
+/// When to stop a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Number of playouts added to the tree.
+    Playouts(u32),
+    /// Time spent searching.
+    Time(Duration),
+}
+
+/// Statistics of a move of the root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveStats {
+    pub play: Move,
+    pub visits: u32,
+    /// Probability of winning for the player to move after this move.
+    pub win_rate: f32,
+    pub prior: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    /// Most visited move, None if the game is over.
+    pub best_move: Option<Move>,
+    /// Probability of winning for the player to move.
+    pub win_rate: f32,
+    /// Number of playouts in the tree, with the ones kept from the previous moves.
+    pub playouts: u32,
+    /// Most visited line of moves from the position.
+    pub principal_variation: Vec<Move>,
+    /// Statistics of the moves of the position, the most visited first.
+    pub moves: Vec<MoveStats>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    play: Move,
+    /// Player of the move leading to the node, the values are for this player.
+    color: Color,
+    prior: f32,
+    visits: u32,
+    wins: f32,
+    amaf_visits: u32,
+    amaf_wins: f32,
+    /// Threads evaluating a leaf under the node, counted as lost playouts.
+    virtual_losses: u32,
+    expanded: bool,
+    children: Vec<usize>,
+}
+
+impl Node {
+    fn new(play: Move, color: Color, prior: f32) -> Self {
+        Node {
+            play,
+            color,
+            prior,
+            visits: 0,
+            wins: 0.,
+            amaf_visits: 0,
+            amaf_wins: 0.,
+            virtual_losses: 0,
+            expanded: false,
+            children: vec![],
+        }
+    }
+
+    fn root(game: &Game) -> Self {
+        Node::new(Move::Pass, !game.turn(), 0.)
+    }
+
+    #[inline]
+    fn win_rate(&self) -> Option<f32> {
+        (self.visits > 0).then(|| self.wins / self.visits as f32)
+    }
+
+    fn stats(&self) -> MoveStats {
+        MoveStats {
+            play: self.play,
+            visits: self.visits,
+            win_rate: self.win_rate().unwrap_or(0.5),
+            prior: self.prior,
+        }
+    }
+}
+
+/// Search tree over a game.
+///
+/// The tree can be searched by several threads with [`Search::run_parallel`].
+pub struct Search<E: Evaluator> {
+    tree: Tree,
+    evaluator: E,
+}
+
+impl<E: Evaluator> Search<E> {
+    pub fn new(game: Game, evaluator: E, config: SearchConfig) -> Self {
+        Search {
+            tree: Tree {
+                config,
+                nodes: vec![Node::root(&game)],
+                game,
+            },
+            evaluator,
+        }
+    }
+
+    /// Position of the root of the tree.
+    #[inline]
+    pub fn game(&self) -> &Game {
+        &self.tree.game
+    }
+
+    #[inline]
+    pub fn config(&self) -> &SearchConfig {
+        &self.tree.config
+    }
+
+    #[inline]
+    pub fn evaluator_mut(&mut self) -> &mut E {
+        &mut self.evaluator
+    }
+
+    /// Number of playouts in the tree.
+    #[inline]
+    pub fn playouts(&self) -> u32 {
+        self.tree.nodes[ROOT].visits
+    }
+
+    /// Searches until the budget is spent, the tree already built is kept.
+    pub fn run(&mut self, budget: Budget) -> SearchResult {
+        match budget {
+            Budget::Playouts(playouts) => {
+                for _ in 0..playouts {
+                    self.playout();
+                }
+            }
+            Budget::Time(duration) => {
+                let start = Instant::now();
+                while start.elapsed() < duration {
+                    self.playout();
+                }
+            }
+        }
+        self.result()
+    }
+
+    /// Adds one playout to the tree: selects a leaf, evaluates it, expands it and updates the
+    /// nodes of the line.
+    pub fn playout(&mut self) {
+        let leaf = self.tree.descend().play(&self.tree.game);
+        let evaluation = (!leaf.game.is_over()).then(|| self.evaluator.evaluate(&leaf.game));
+        self.tree.update(leaf, evaluation);
+    }
+
+    /// Moves the root of the tree to the move, the subtree of the move is kept.
+    ///
+    /// # Panics
+    ///
+    /// Like [`Game::play`].
+    pub fn advance(&mut self, play: Move) {
+        self.tree.advance(play);
+    }
+
+    /// Most visited move of the root, None if no move was searched.
+    pub fn best_move(&self) -> Option<Move> {
+        self.tree.best_move()
+    }
+
+    /// Probability of winning for the player to move, with the best move.
+    pub fn win_rate(&self) -> f32 {
+        self.tree.win_rate()
+    }
+
+    /// Line of the most visited moves from the root.
+    pub fn principal_variation(&self) -> Vec<Move> {
+        self.tree.principal_variation()
+    }
+
+    /// Statistics of the moves of the root, the most visited first.
+    pub fn moves(&self) -> Vec<MoveStats> {
+        self.tree.moves()
+    }
+
+    pub fn result(&self) -> SearchResult {
+        SearchResult {
+            best_move: self.best_move(),
+            win_rate: self.win_rate(),
+            playouts: self.playouts(),
+            principal_variation: self.principal_variation(),
+            moves: self.moves(),
+        }
+    }
+}
+
+/// Line selected in the tree, from the root to a leaf.
+struct Line {
+    /// Nodes from the root to the leaf.
+    path: Vec<usize>,
+    /// Moves played from the root.
+    sequence: Vec<(Color, Move)>,
+}
+
+impl Line {
+    /// Plays the moves of the line on a copy of the root, out of the tree so the threads of a
+    /// parallel search don't copy the game under the lock.
+    fn play(self, root: &Game) -> Leaf {
+        let mut game = root.clone();
+        for &(_, play) in &self.sequence {
+            game.play(play);
+        }
+        Leaf {
+            game,
+            path: self.path,
+            sequence: self.sequence,
+        }
+    }
+}
+
+struct Leaf {
+    game: Game,
+    path: Vec<usize>,
+    sequence: Vec<(Color, Move)>,
+}
+
+struct Tree {
+    config: SearchConfig,
+    game: Game,
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    /// Selects a leaf from the root, a virtual loss is added to the nodes of the line until
+    /// the leaf is updated so other threads explore other lines.
+    /// The nodes of the finished games are never expanded, so the line stops on them.
+    fn descend(&mut self) -> Line {
+        let mut line = Line {
+            path: vec![ROOT],
+            sequence: vec![],
+        };
+        let mut node = ROOT;
+        self.nodes[ROOT].virtual_losses += 1;
+        while self.nodes[node].expanded {
+            let Some(child) = self.select(node) else {
+                break;
+            };
+            let Node { play, color, .. } = self.nodes[child];
+            self.nodes[child].virtual_losses += 1;
+            line.sequence.push((color, play));
+            line.path.push(child);
+            node = child;
+        }
+        line
+    }
+
+    /// Expands the leaf and updates the line with its evaluation, None if the game is over.
+    /// The leaf is already expanded if another thread evaluated it first.
+    fn update(&mut self, mut leaf: Leaf, evaluation: Option<Evaluation>) {
+        let turn = leaf.game.turn();
+        let value = match evaluation {
+            Some(evaluation) => {
+                let node = *leaf.path.last().unwrap();
+                if !self.nodes[node].expanded {
+                    self.expand(node, &leaf.game, &evaluation.priors);
+                }
+                leaf.sequence.extend(evaluation.played);
+                evaluation.value
+            }
+            None => value_for(&leaf.game, turn),
+        };
+        self.backpropagate(&leaf.path, &leaf.sequence, turn, value);
+    }
+
+    fn advance(&mut self, play: Move) {
+        let child = self.nodes[ROOT]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].play == play);
+        self.game.play(play);
+        self.nodes = match child {
+            Some(child) => self.subtree(child),
+            None => vec![Node::root(&self.game)],
+        };
+    }
+
+    fn best_move(&self) -> Option<Move> {
+        self.best_child(ROOT).map(|child| self.nodes[child].play)
+    }
+
+    fn win_rate(&self) -> f32 {
+        match self.best_child(ROOT) {
+            Some(child) => self.nodes[child].win_rate().unwrap_or(0.5),
+            None if self.game.is_over() => value_for(&self.game, self.game.turn()),
+            None => 0.5,
+        }
+    }
+
+    fn principal_variation(&self) -> Vec<Move> {
+        let mut variation = vec![];
+        let mut node = ROOT;
+        while let Some(child) = self.best_child(node) {
+            variation.push(self.nodes[child].play);
+            node = child;
+        }
+        variation
+    }
+
+    fn moves(&self) -> Vec<MoveStats> {
+        let mut moves: Vec<MoveStats> = self.nodes[ROOT]
+            .children
+            .iter()
+            .map(|&child| self.nodes[child].stats())
+            .collect();
+        moves.sort_by_key(|stats| std::cmp::Reverse(stats.visits));
+        moves
+    }
+
+    fn best_child(&self, node: usize) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .filter(|&child| self.nodes[child].visits > 0)
+            .max_by(|&a, &b| {
+                let (a, b) = (&self.nodes[a], &self.nodes[b]);
+                a.visits.cmp(&b.visits).then(a.wins.total_cmp(&b.wins))
+            })
+    }
+
+    /// Child with the best UCT value mixed with its RAVE value.
+    fn select(&self, node: usize) -> Option<usize> {
+        let parent = &self.nodes[node];
+        let parent_visits = ((parent.visits + parent.virtual_losses).max(1) as f32).ln();
+        self.nodes[node].children.iter().copied().max_by(|&a, &b| {
+            self.score(a, parent_visits)
+                .total_cmp(&self.score(b, parent_visits))
+        })
+    }
+
+    fn score(&self, node: usize, ln_parent_visits: f32) -> f32 {
+        let config = &self.config;
+        let node = &self.nodes[node];
+        let visits = (node.visits + node.virtual_losses) as f32;
+        let mut value = if visits > 0. {
+            node.wins / visits
+        } else if node.play == Move::Pass {
+            config.pass_urgency
+        } else {
+            config.first_play_urgency
+        };
+        if config.rave_equivalence > 0. && node.amaf_visits > 0 {
+            let amaf = node.amaf_wins / node.amaf_visits as f32;
+            let beta = (config.rave_equivalence / (3. * visits + config.rave_equivalence)).sqrt();
+            value = (1. - beta) * value.min(1.) + beta * amaf;
+        }
+        value
+            + config.exploration * (ln_parent_visits / (visits + 1.)).sqrt()
+            + config.prior_weight * node.prior / (visits + 1.)
+    }
+
+    fn expand(&mut self, node: usize, game: &Game, priors: &[(Move, f32)]) {
+        let size = game.size();
+        let mut priors_by_index = vec![0.; policy_len(size)];
+        for &(play, prior) in priors {
+            if let Some(index) = move_to_index(play, size) {
+                priors_by_index[index] = prior;
+            }
+        }
+        let moves = game
+            .legals_by(game.rule().flag_illegal | IllegalRules::FILLEYE)
+            .map(Move::from)
+            .chain(std::iter::once(Move::Pass));
+        let start = self.nodes.len();
+        self.nodes.extend(moves.map(|play| {
+            let prior = move_to_index(play, size).map_or(0., |index| priors_by_index[index]);
+            Node::new(play, game.turn(), prior)
+        }));
+        let end = self.nodes.len();
+        let node = &mut self.nodes[node];
+        node.children = (start..end).collect();
+        node.expanded = true;
+    }
+
+    /// Updates the nodes of the path with the value for the player `turn`, and the RAVE values
+    /// of their children played later in the sequence by the same player.
+    fn backpropagate(
+        &mut self,
+        path: &[usize],
+        sequence: &[(Color, Move)],
+        turn: Color,
+        value: f32,
+    ) {
+        let for_player = |color: Color| if color == turn { value } else { 1. - value };
+        let mut played_after: HashSet<(Color, Move)> = sequence[path.len() - 1..]
+            .iter()
+            .copied()
+            .filter(|&(_, play)| play != Move::Pass)
+            .collect();
+        for (depth, &node) in path.iter().enumerate().rev() {
+            if depth < path.len() - 1 && sequence[depth].1 != Move::Pass {
+                played_after.insert(sequence[depth]);
+            }
+            let color = self.nodes[node].color;
+            self.nodes[node].visits += 1;
+            self.nodes[node].virtual_losses -= 1;
+            self.nodes[node].wins += for_player(color);
+            if self.config.rave_equivalence <= 0. {
+                continue;
+            }
+            for i in 0..self.nodes[node].children.len() {
+                let child = self.nodes[node].children[i];
+                let child = &mut self.nodes[child];
+                if played_after.contains(&(child.color, child.play)) {
+                    child.amaf_visits += 1;
+                    child.amaf_wins += for_player(child.color);
+                }
+            }
+        }
+    }
+
+    /// Copy of the subtree of the node, with the node as root.
+    fn subtree(&self, root: usize) -> Vec<Node> {
+        let mut nodes = vec![self.nodes[root].clone()];
+        let mut i = 0;
+        while i < nodes.len() {
+            let children = std::mem::take(&mut nodes[i].children);
+            let start = nodes.len();
+            nodes.extend(children.iter().map(|&child| self.nodes[child].clone()));
+            nodes[i].children = (start..nodes.len()).collect();
+            i += 1;
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pieces::stones::Stone;
+
+    #[test]
+    fn search() {
+        // White captures the three black stones or extends, before losing its own three stones
+        let mut builder = Game::builder();
+        builder.size((5, 5)).turn(Color::White);
+        for coord in [(0, 0), (0, 1), (0, 2), (2, 0), (2, 1), (2, 2)] {
+            builder.add(Stone {
+                coord,
+                color: Color::Black,
+            });
+        }
+        for coord in [(1, 0), (1, 1), (1, 2)] {
+            builder.add(Stone {
+                coord,
+                color: Color::White,
+            });
+        }
+        let game = builder.build().unwrap();
+        let mut search = Search::new(game, RandomPlayout::with_seed(1), SearchConfig::default());
+        let result = search.run(Budget::Playouts(300));
+        let best_move = result.best_move.unwrap();
+        assert!([Move::Play(0, 3), Move::Play(1, 3)].contains(&best_move));
+        assert_eq!(result.playouts, 300);
+        assert_eq!(result.moves[0].play, best_move);
+        assert_eq!(result.principal_variation[0], best_move);
+        assert_eq!(result.win_rate, result.moves[0].win_rate);
+        assert!(result.win_rate > 0.5);
+        assert!(result.moves.iter().any(|stats| stats.play == Move::Pass));
+
+        let visits = result.moves[0].visits;
+        search.advance(best_move);
+        assert_eq!(search.playouts(), visits);
+        assert_eq!(search.game().turn(), Color::Black);
+        search.run(Budget::Time(Duration::from_millis(10)));
+        assert!(search.playouts() > visits);
+
+        // A move outside the tree starts a new one
+        let play = search.moves().last().unwrap().play;
+        search.advance(play);
+        search.advance(Move::Pass);
+        assert_eq!(search.playouts(), 0);
+        assert_eq!(search.best_move(), None);
+    }
+}
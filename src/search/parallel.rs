@@ -0,0 +1,113 @@
+//! Module with the searches and the playouts on several threads.
+//!
+//! The threads share the tree behind a lock held only to select and to update a line, the
+//! moves of the line are played and evaluated in parallel. A virtual loss on the lines being
+//! evaluated spreads the threads over different lines. [`Game`] is `Send` and `Sync`, so the
+//! positions are evaluated on the threads without copying the moves already played.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use crate::rules::game::Game;
+use crate::search::{Budget, Evaluation, Evaluator, Search, SearchResult};
+
+impl<E: Evaluator + Clone + Send> Search<E> {
+    /// Searches on `threads` threads until the budget is spent, each thread has a fork of the
+    /// evaluator. The tree already built is kept.
+    ///
+    /// Only the selection of the lines and the updates hold the lock, so the search scales with
+    /// the threads when the evaluations cost more than these, like playouts on big gobans or a
+    /// neural network. Cheap evaluators spend their time waiting for the lock.
+    pub fn run_parallel(&mut self, budget: Budget, threads: usize) -> SearchResult {
+        let start = Instant::now();
+        let started = AtomicU32::new(0);
+        let evaluators: Vec<E> = (0..threads.max(1)).map(|_| self.evaluator.fork()).collect();
+        let root = self.tree.game.clone();
+        let tree = Mutex::new(&mut self.tree);
+        thread::scope(|scope| {
+            for mut evaluator in evaluators {
+                let (tree, started, root) = (&tree, &started, &root);
+                scope.spawn(move || loop {
+                    let done = match budget {
+                        Budget::Playouts(playouts) => {
+                            started.fetch_add(1, Ordering::Relaxed) >= playouts
+                        }
+                        Budget::Time(duration) => start.elapsed() >= duration,
+                    };
+                    if done {
+                        break;
+                    }
+                    let line = tree.lock().unwrap().descend();
+                    let leaf = line.play(root);
+                    let evaluation = (!leaf.game.is_over()).then(|| evaluator.evaluate(&leaf.game));
+                    tree.lock().unwrap().update(leaf, evaluation);
+                });
+            }
+        });
+        self.result()
+    }
+}
+
+/// Evaluates the position `count` times on `threads` threads, each thread has a fork of the
+/// evaluator. The evaluations are in the order of the threads.
+pub fn evaluate_parallel<E: Evaluator + Clone + Send>(
+    game: &Game,
+    evaluator: &mut E,
+    count: usize,
+    threads: usize,
+) -> Vec<Evaluation> {
+    let threads = threads.max(1);
+    let evaluators: Vec<E> = (0..threads).map(|_| evaluator.fork()).collect();
+    thread::scope(|scope| {
+        let handles: Vec<_> = evaluators
+            .into_iter()
+            .enumerate()
+            .map(|(thread, mut evaluator)| {
+                let batch = count / threads + usize::from(thread < count % threads);
+                scope.spawn(move || {
+                    (0..batch)
+                        .map(|_| evaluator.evaluate(game))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{GobanSizes, CHINESE};
+    use crate::search::{RandomPlayout, SearchConfig};
+
+    #[test]
+    fn parallel_search() {
+        let game = Game::new(GobanSizes::Custom(5, 5), CHINESE);
+        let mut search = Search::new(
+            game.clone(),
+            RandomPlayout::with_seed(3),
+            SearchConfig::default(),
+        );
+        let result = search.run_parallel(Budget::Playouts(200), 4);
+        assert_eq!(result.playouts, 200);
+        // The threads starting before the root is expanded only visit the root
+        let visits: u32 = result.moves.iter().map(|stats| stats.visits).sum();
+        assert!((196..200).contains(&visits));
+        assert!(search
+            .tree
+            .nodes
+            .iter()
+            .all(|node| node.virtual_losses == 0));
+        assert_eq!(result.principal_variation[0], result.best_move.unwrap());
+
+        let evaluations = evaluate_parallel(&game, &mut RandomPlayout::with_seed(3), 10, 4);
+        assert_eq!(evaluations.len(), 10);
+        assert_ne!(evaluations[0].played, evaluations[9].played);
+    }
+}
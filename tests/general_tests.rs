@@ -2,13 +2,19 @@
 mod tests {
     use rand::prelude::IndexedRandom;
     use rand::rng;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
 
     use goban::pieces::goban::Goban;
     use goban::pieces::stones::{Color, Stone, EMPTY};
+    use goban::rules::clock::{GameClock, ManualTime, TimeControl, TimeSource};
     use goban::rules::game::Game;
+    use goban::rules::sgf_bridge::{to_sgf_collection, SgfError, SgfReplay};
+    use goban::rules::super_ko::SuperKoCheck;
     use goban::rules::Move::Play;
     use goban::rules::PlayError::Suicide;
-    use goban::rules::{EndGame, GobanSizes, Move, PlayError};
+    use goban::rules::{EndGame, GobanSizes, Move, PlayError, Rule};
     use goban::rules::{CHINESE, JAPANESE};
 
     #[test]
@@ -431,6 +437,152 @@ mod tests {
         assert_eq!(g.goban().get_color((0, 0)), EMPTY);
     }
 
+    #[test]
+    fn move_record() {
+        let mut g = Game::new(GobanSizes::Nine, CHINESE);
+        assert!(g.last_move().is_none());
+        g.play(Move::Play(1, 0)); // B
+        g.play(Move::Play(0, 0)); // W
+        g.play(Move::Play(0, 1)); // B captures
+        g.play(Move::Pass); // W
+
+        let record = g.record();
+        assert_eq!(record.len(), 4);
+        let numbers: Vec<_> = record.iter().map(|r| r.number).collect();
+        assert_eq!(numbers, vec![1, 2, 3, 4]);
+
+        let capture = record.get(3).unwrap();
+        assert_eq!(capture.color, Color::Black);
+        assert_eq!(capture.play, Move::Play(0, 1));
+        assert_eq!(capture.ko_point, Some((0, 0)));
+        assert_eq!(
+            record.captures(capture),
+            &[Stone {
+                coord: (0, 0),
+                color: Color::White
+            }]
+        );
+        assert!(record.captures(record.get(1).unwrap()).is_empty());
+
+        let last = g.last_move().unwrap();
+        assert_eq!(last.play, Move::Pass);
+        assert_eq!(last.color, Color::White);
+        assert_eq!(last.hash, g.goban().zobrist_hash());
+        assert_eq!(last.number_of_captures(), 0);
+    }
+
+    #[test]
+    fn with_move() {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        let mut played = Game::new(GobanSizes::Nine, CHINESE);
+        let mut gobans = vec![game.goban().clone()];
+        let mut rng = rng();
+        while !game.is_over() && game.record().len() < 100 {
+            let legals: Vec<_> = game.legals().collect();
+            let m = legals.choose(&mut rng).map_or(Move::Pass, |&c| c.into());
+            let next = game.with_move(m);
+            assert_eq!(next.record().len(), game.record().len() + 1);
+            assert_eq!(game.record().last(), played.last_move());
+            played.play(m);
+            game = next;
+            gobans.push(game.goban().clone());
+        }
+        assert_eq!(game.goban(), played.goban());
+        assert_eq!(game.record(), played.record());
+        for (number, goban) in gobans.iter().enumerate() {
+            assert_eq!(&game.goban_at(number as u32), goban);
+        }
+        let last = game.last_gobans(8);
+        assert_eq!(last.len(), 9);
+        for (i, goban) in last.iter().enumerate() {
+            assert_eq!(goban, &gobans[gobans.len() - 1 - i]);
+        }
+        assert_eq!(game.last_gobans(1000).len(), gobans.len());
+
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.play(Move::Play(4, 4));
+        assert_eq!(
+            game.try_with_move(Move::Play(4, 4)).err(),
+            Some(PlayError::PointNotEmpty)
+        );
+        let mut other = game.with_move(Move::Play(3, 3));
+        other.info_mut().black_player = Some("Black".to_string());
+        assert!(game.info().black_player.is_none());
+        assert_eq!(game.record().len(), 1);
+    }
+
+    #[test]
+    fn send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Game>();
+        assert_send_sync::<Goban>();
+
+        let game = Game::new(GobanSizes::Nine, CHINESE);
+        let next = std::thread::spawn(move || game.with_move(Move::Play(4, 4)))
+            .join()
+            .unwrap();
+        assert_eq!(next.record().len(), 1);
+    }
+
+    #[test]
+    fn lose_on_time() {
+        let time = Arc::new(ManualTime::default());
+        let mut g = Game::builder()
+            .size((9, 9))
+            .clock(GameClock::new(
+                TimeControl::ByoYomi {
+                    main_time: Duration::from_secs(30),
+                    period_time: Duration::from_secs(10),
+                    periods: 1,
+                },
+                time.clone(),
+            ))
+            .build()
+            .unwrap();
+        time.advance(Duration::from_secs(35));
+        g.try_play(Move::Play(4, 4)).unwrap();
+        assert_eq!(g.gtp_time_left(Color::Black), Some((10, 1)));
+        assert_eq!(g.gtp_time_left(Color::White), Some((30, 0)));
+        g.set_time_left(Color::White, Duration::from_secs(5), 1);
+
+        time.advance(Duration::from_secs(16));
+        g.play(Move::Play(3, 3));
+        assert_eq!(g.outcome(), Some(EndGame::WinnerByTime(Color::Black)));
+        assert_eq!(g.goban().get_color((3, 3)), EMPTY);
+        assert_eq!(g.try_play(Move::Pass).err(), Some(PlayError::GameOver));
+    }
+
+    /// Time passing by steps on each reading.
+    #[derive(Debug, Default)]
+    struct SteppingTime(AtomicU64);
+
+    impl TimeSource for SteppingTime {
+        fn now(&self) -> Duration {
+            Duration::from_secs(20 * self.0.fetch_add(1, Ordering::Relaxed))
+        }
+    }
+
+    #[test]
+    fn lose_on_time_while_checking() {
+        let clock = GameClock::new(
+            TimeControl::Absolute {
+                main_time: Duration::from_secs(30),
+            },
+            Arc::new(SteppingTime::default()),
+        );
+        let mut g = Game::builder().size((9, 9)).clock(clock).build().unwrap();
+        // In time when the move is checked, out of time when the clock is punched
+        assert_eq!(
+            g.try_play(Move::Play(4, 4)).err(),
+            Some(PlayError::GameOver)
+        );
+        assert_eq!(g.outcome(), Some(EndGame::WinnerByTime(Color::White)));
+        assert_eq!(g.goban().get_color((4, 4)), EMPTY);
+
+        g.resume();
+        assert!(!g.clock().unwrap().is_running());
+    }
+
     #[test]
     fn game_finished() {
         let mut g = Game::new(GobanSizes::Nine, CHINESE);
@@ -563,16 +715,90 @@ mod tests {
 
         let mut game = Game::from_sgf(&sgf).unwrap();
         println!("{}", game.pretty_string());
+        let mut verified = game.clone();
+        assert_eq!(verified.super_ko_check(), SuperKoCheck::Hash);
+        verified.set_super_ko_check(SuperKoCheck::Verified);
+        let mut wide = game.clone();
+        assert_eq!(wide.wide_position_hash(), None);
+        wide.set_super_ko_check(SuperKoCheck::WideHash);
 
         for &m in &[Play(6, 5), Play(6, 3)] {
             game.play(m);
+            verified.play(m);
+            wide.play(m);
             println!("{}", game.pretty_string());
         }
+        let keys = wide.wide_keys().unwrap().clone();
+        assert_eq!(wide.wide_position_hash(), Some(wide.position_hash(&*keys)));
+        assert_ne!(
+            wide.wide_position_hash(),
+            wide.with_move(Move::Pass).wide_position_hash()
+        );
 
         assert!(game.check_super_ko(Stone {
             coord: (6, 4),
             color: Color::Black
-        }))
+        }));
+        assert!(verified.check_super_ko(Stone {
+            coord: (6, 4),
+            color: Color::Black
+        }));
+        assert!(!verified.check_super_ko(Stone {
+            coord: (0, 0),
+            color: Color::Black
+        }));
+        assert!(wide.check_super_ko(Stone {
+            coord: (6, 4),
+            color: Color::Black
+        }));
+    }
+
+    #[test]
+    fn triple_ko_super_ko() {
+        // Three kos, the last recapture repeats the position of the start
+        let black = [(1, 0), (0, 1), (2, 1), (1, 5), (0, 6), (2, 6)];
+        let black = black.into_iter().chain([(4, 2), (5, 1), (6, 2), (5, 3)]);
+        let white = [(0, 2), (1, 1), (2, 2), (1, 3), (0, 7), (1, 6), (2, 7)];
+        let white = white.into_iter().chain([(1, 8), (5, 0), (4, 1), (6, 1)]);
+        let cycle = [(1, 2), (5, 2), (1, 7), (1, 1), (5, 1)];
+        let repetition = Stone {
+            coord: (1, 6),
+            color: Color::White,
+        };
+        let checks = [
+            SuperKoCheck::Hash,
+            SuperKoCheck::Verified,
+            SuperKoCheck::WideHash,
+        ];
+        for check in checks {
+            let mut builder = Game::builder();
+            builder.size((9, 9)).rule(CHINESE).super_ko_check(check);
+            for (coord, color) in black
+                .clone()
+                .map(|coord| (coord, Color::Black))
+                .chain(white.clone().map(|coord| (coord, Color::White)))
+            {
+                builder.add(Stone { coord, color });
+            }
+            let mut game = builder.build().unwrap();
+            assert_eq!(game.super_ko_check(), check);
+            let start = game.goban().clone();
+            for (x, y) in cycle {
+                assert!(!game.check_super_ko(Stone {
+                    coord: (x, y),
+                    color: game.turn(),
+                }));
+                game.try_play(Play(x, y)).unwrap();
+            }
+            assert!(!game.check_ko(repetition));
+            assert!(game.check_super_ko(repetition), "{check:?}");
+            assert_eq!(
+                game.try_play(Play(1, 6)).err(),
+                Some(PlayError::Ko),
+                "{check:?}"
+            );
+            assert_eq!(game.play_for_verification((1, 6)), start);
+        }
     }
 
     /// https://github.com/Sagebati/goban/issues/6
@@ -674,6 +900,375 @@ mod tests {
         assert_eq!(game.try_play(Move::Play(0, 0)).err(), Some(Suicide));
     }
 
+    #[test]
+    fn try_play_out_of_bounds() {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        assert_eq!(
+            game.try_play(Move::Play(9, 0)).err(),
+            Some(PlayError::OutOfBounds)
+        );
+        assert_eq!(
+            game.try_play(Move::Play(3, 200)).err(),
+            Some(PlayError::OutOfBounds)
+        );
+        assert_eq!(game.turn(), Color::Black);
+        assert_eq!(game.goban().number_of_stones(), (0, 0));
+    }
+
+    #[test]
+    fn try_play_after_resign() {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        assert_eq!(
+            game.try_play(Move::Resign(Color::White)).err(),
+            Some(PlayError::WrongColor)
+        );
+        game.try_play(Move::Resign(Color::Black)).unwrap();
+        assert_eq!(game.outcome(), Some(EndGame::WinnerByResign(Color::White)));
+        assert_eq!(
+            game.try_play(Move::Play(4, 4)).err(),
+            Some(PlayError::GameOver)
+        );
+        assert_eq!(game.try_play(Move::Pass).err(), Some(PlayError::GameOver));
+        assert_eq!(
+            game.try_play(Move::Resign(Color::Black)).err(),
+            Some(PlayError::GameOver)
+        );
+    }
+
+    #[test]
+    fn resign_outcome() {
+        for color in [Color::Black, Color::White] {
+            let mut game = Game::new(GobanSizes::Nine, CHINESE);
+            game.play(Move::Resign(color));
+            assert!(game.is_over());
+            assert_eq!(game.outcome(), Some(EndGame::WinnerByResign(!color)));
+            assert_eq!(game.outcome().unwrap().get_winner(), Some(!color));
+        }
+    }
+
+    #[test]
+    fn try_play_after_two_passes() {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.try_play(Move::Pass).unwrap();
+        game.try_play(Move::Pass).unwrap();
+        assert_eq!(game.try_play(Move::Pass).err(), Some(PlayError::GamePaused));
+        assert_eq!(
+            game.try_play(Move::Resign(Color::Black)).err(),
+            Some(PlayError::GamePaused)
+        );
+        game.resume();
+        game.try_play(Move::Play(4, 4)).unwrap();
+    }
+
+    #[test]
+    fn sgf_game_info() {
+        let sgf = "(;GM[1]FF[4]SZ[9]KM[6.5]PB[Honinbo Shusaku]BR[7d]PW[Inoue Genan Inseki]WR[8d]
+        DT[1846-09-11]EV[Castle game]GN[Ear reddening game]PC[Osaka]C[A famous game]XX[unknown]
+        ;B[ee];W[cc])";
+        let game = Game::from_sgf(sgf).unwrap();
+        let info = game.info();
+        assert_eq!(info.black_player.as_deref(), Some("Honinbo Shusaku"));
+        assert_eq!(info.black_rank.as_deref(), Some("7d"));
+        assert_eq!(info.white_player.as_deref(), Some("Inoue Genan Inseki"));
+        assert_eq!(info.white_rank.as_deref(), Some("8d"));
+        assert_eq!(info.date.as_deref(), Some("1846-09-11"));
+        assert_eq!(info.event.as_deref(), Some("Castle game"));
+        assert_eq!(info.game_name.as_deref(), Some("Ear reddening game"));
+        assert_eq!(info.place.as_deref(), Some("Osaka"));
+        assert_eq!(info.comment.as_deref(), Some("A famous game"));
+        assert_eq!(
+            info.unknown_properties,
+            vec![("XX".to_string(), "unknown".to_string())]
+        );
+
+        let game = Game::builder()
+            .black_player("B")
+            .white_player("W")
+            .event("Test")
+            .build()
+            .unwrap();
+        assert_eq!(game.info().black_player.as_deref(), Some("B"));
+        assert_eq!(game.info().white_player.as_deref(), Some("W"));
+        assert_eq!(game.info().event.as_deref(), Some("Test"));
+        assert_eq!(game.info().date, None);
+    }
+
+    #[test]
+    fn setup_stones() {
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.play(Play(0, 0)).play(Play(4, 4)).play(Play(0, 1));
+        game.add_stones(&[
+            Stone {
+                coord: (0, 2),
+                color: Color::Black,
+            },
+            Stone {
+                coord: (4, 4),
+                color: Color::Black,
+            },
+        ]);
+        assert_eq!(game.goban().get_color((4, 4)), Some(Color::Black));
+        assert_eq!(game.goban().chains().count(), 2);
+        // Erasing the middle stone splits the chain
+        game.erase_stones(&[(0, 1)]);
+        assert_eq!(game.goban().get_color((0, 1)), None);
+        assert_eq!(game.goban().chains().count(), 3);
+        game.set_turn(Color::Black);
+        assert_eq!(game.turn(), Color::Black);
+        assert_eq!(game.prisoners(), (0, 0));
+
+        let setups = game.record().setups();
+        assert_eq!(setups.len(), 1);
+        assert_eq!(setups[0].after_move, 3);
+        assert_eq!(setups[0].added.len(), 2);
+        assert_eq!(
+            setups[0].erased,
+            vec![
+                Stone {
+                    coord: (4, 4),
+                    color: Color::White
+                },
+                Stone {
+                    coord: (0, 1),
+                    color: Color::Black
+                }
+            ]
+        );
+        assert_eq!(setups[0].turn, Some(Color::Black));
+    }
+
+    #[test]
+    fn sgf_setup_nodes() {
+        let sgf = "(;GM[1]FF[4]SZ[9]KM[6.5];B[ee];W[cc];AE[ee][cc]AB[dd]AW[ff]PL[W];W[gg];B[aa];B[ba])";
+        let game = Game::from_sgf(sgf).unwrap();
+        let goban = game.goban();
+        assert_eq!(goban.get_color((4, 4)), None);
+        assert_eq!(goban.get_color((2, 2)), None);
+        assert_eq!(goban.get_color((3, 3)), Some(Color::Black));
+        assert_eq!(goban.get_color((5, 5)), Some(Color::White));
+        assert_eq!(goban.get_color((6, 6)), Some(Color::White));
+        assert_eq!(goban.get_color((0, 0)), Some(Color::Black));
+        // The color of the move is taken from the SGF
+        assert_eq!(goban.get_color((0, 1)), Some(Color::Black));
+        assert_eq!(game.turn(), Color::White);
+        assert_eq!(game.record().len(), 5);
+        assert_eq!(game.record().setups()[0].after_move, 2);
+    }
+
+    #[test]
+    fn sgf_compressed_points() {
+        let sgf = "(;GM[1]FF[4]SZ[9]AB[aa:bb]AW[ee];AE[aa:ab]AW[gh:hg];B[ii])";
+        let game = Game::from_sgf(sgf).unwrap();
+        let goban = game.goban();
+        assert_eq!(game.goban_at(0).get_stones().count(), 5);
+        assert_eq!(goban.get_color((0, 0)), None);
+        assert_eq!(goban.get_color((1, 0)), None);
+        assert_eq!(goban.get_color((0, 1)), Some(Color::Black));
+        assert_eq!(goban.get_color((1, 1)), Some(Color::Black));
+        assert_eq!(goban.get_color((4, 4)), Some(Color::White));
+        for coord in [(6, 6), (6, 7), (7, 6), (7, 7)] {
+            assert_eq!(goban.get_color(coord), Some(Color::White));
+        }
+        assert_eq!(goban.get_color((8, 8)), Some(Color::Black));
+    }
+
+    #[test]
+    fn sgf_resign_round_trip() {
+        let mut game = Game::new(GobanSizes::Nine, JAPANESE);
+        game.play(Play(4, 4))
+            .play(Play(2, 2))
+            .play(Move::Resign(Color::Black));
+        let read = Game::from_sgf(&game.to_sgf()).unwrap();
+        assert!(read.to_sgf().contains("RE[W+R]"));
+        assert_eq!(read.outcome(), game.outcome());
+        assert_eq!(read.record(), game.record());
+        assert_eq!(
+            read.record().last().map(|record| record.play),
+            Some(Move::Resign(Color::Black))
+        );
+    }
+
+    #[test]
+    fn sgf_pl_only_node() {
+        // Black takes the ko, a PL node giving the turn to white changes nothing
+        let moves = "(;GM[1]FF[4]SZ[9];B[ba];W[ca];B[ab];W[cc];B[bc];W[db];B[ia];W[bb];B[cb]";
+        let game = Game::from_sgf(&format!("{moves})")).unwrap();
+        assert_eq!(game.ko_point(), Some((1, 1)));
+        let with_pl = Game::from_sgf(&format!("{moves};PL[W])")).unwrap();
+        assert_eq!(with_pl.ko_point(), Some((1, 1)));
+        assert_eq!(with_pl.record(), game.record());
+        assert!(with_pl.record().setups().is_empty());
+        assert_eq!(
+            with_pl.clone().try_play(Play(1, 1)).err(),
+            Some(PlayError::Ko)
+        );
+    }
+
+    #[test]
+    fn sgf_collection() {
+        let sgf = "(;GM[1]FF[4]SZ[9]PB[First];B[ee];W[cc])
+        (;GM[1]FF[4]SZ[9]RU[AGA];B[ee])
+        (;GM[1]FF[4]SZ[13]PB[Third]C[a \\] b](;B[aa])(;B[bb]))
+        (;GM[1]FF[4]SZ[9];B[ee]";
+        let games = Game::from_sgf_collection(sgf, SgfReplay::Strict);
+        assert_eq!(games.len(), 4);
+        let first = &games[0].as_ref().unwrap().game;
+        assert_eq!(first.info().black_player.as_deref(), Some("First"));
+        assert_eq!(first.record().len(), 2);
+        assert!(matches!(games[1], Err(SgfError::InvalidGame(_))));
+        let third = &games[2].as_ref().unwrap().game;
+        assert_eq!(third.size(), (13, 13));
+        assert_eq!(third.info().comment.as_deref(), Some("a ] b"));
+        assert_eq!(third.record().len(), 1);
+        assert!(matches!(games[3], Err(SgfError::Parse(_))));
+
+        let written = to_sgf_collection([first, third]);
+        let games = Game::from_sgf_collection(&written, SgfReplay::Strict);
+        assert_eq!(games.len(), 2);
+        let third_again = &games[1].as_ref().unwrap().game;
+        assert_eq!(third_again.info(), third.info());
+        assert_eq!(third_again.goban(), third.goban());
+    }
+
+    #[test]
+    fn sgf_write_read() {
+        let game = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();
+        let read = Game::from_sgf(&game.to_sgf()).unwrap();
+        assert_eq!(read.goban(), game.goban());
+        assert_eq!(read.record(), game.record());
+        assert_eq!(read.info(), game.info());
+        assert_eq!(read.outcome(), game.outcome());
+        assert_eq!(read.prisoners(), game.prisoners());
+        assert_eq!(read.komi(), game.komi());
+
+        // With handicap, captures and setups
+        let mut game = Game::builder()
+            .size((9, 9))
+            .put_handicap(&[(2, 2)])
+            .build()
+            .unwrap();
+        game.play(Play(0, 1))
+            .play(Play(0, 0))
+            .play(Play(1, 0))
+            .play(Play(5, 5));
+        assert_eq!(game.prisoners(), (0, 1));
+        game.erase_stones(&[(2, 2)]).set_turn(Color::Black);
+        game.play(Play(3, 3)).play(Move::Pass);
+        assert_eq!(game.goban_at(0).get_stones().count(), 1);
+        assert_eq!(game.goban_at(3).get_stones().count(), 3);
+        assert_eq!(game.goban_at(4).get_color((2, 2)), Some(Color::Black));
+        assert_eq!(game.goban_at(5).get_color((2, 2)), None);
+
+        let read = Game::from_sgf_with(&game.to_sgf(), SgfReplay::Strict)
+            .unwrap()
+            .game;
+        assert_eq!(read.handicap(), 1);
+        assert_eq!(read.goban(), game.goban());
+        assert_eq!(read.turn(), game.turn());
+        assert_eq!(read.record(), game.record());
+        assert_eq!(read.prisoners(), game.prisoners());
+    }
+
+    #[test]
+    fn sgf_non_square() {
+        // 9 columns and 5 lines
+        let sgf = "(;GM[1]FF[4]SZ[9:5];B[ie];W[ae])";
+        let game = Game::from_sgf_with(sgf, SgfReplay::Strict).unwrap().game;
+        assert_eq!(game.size(), (5, 9));
+        assert_eq!(game.goban().get_color((4, 8)), Some(Color::Black));
+        assert_eq!(game.goban().get_color((4, 0)), Some(Color::White));
+
+        let written = game.to_sgf();
+        assert!(written.contains("SZ[9:5]"));
+        let read = Game::from_sgf_with(&written, SgfReplay::Strict)
+            .unwrap()
+            .game;
+        assert_eq!(read.size(), game.size());
+        assert_eq!(read.goban(), game.goban());
+        assert_eq!(read.record(), game.record());
+    }
+
+    #[test]
+    fn sgf_strict_and_lenient_replay() {
+        let sgf = "(;GM[1]FF[4]SZ[9]KM[6.5];B[ee];W[ee];W[cc];B[gg])";
+        match Game::from_sgf_with(sgf, SgfReplay::Strict) {
+            Err(SgfError::IllegalMove(diagnostic)) => {
+                assert_eq!(diagnostic.node, 2);
+                assert_eq!(diagnostic.move_number, 2);
+                assert_eq!(diagnostic.play, Play(4, 4));
+                assert_eq!(diagnostic.error, PlayError::PointNotEmpty);
+            }
+            other => panic!("expected an illegal move, got {other:?}"),
+        }
+
+        let import = Game::from_sgf_with(sgf, SgfReplay::Lenient).unwrap();
+        assert_eq!(import.warnings.len(), 1);
+        assert!(import.warnings[0].skipped);
+        assert_eq!(import.game.record().len(), 3);
+        assert_eq!(import.game.goban().get_color((2, 2)), Some(Color::White));
+        assert_eq!(import.game.goban().get_color((6, 6)), Some(Color::Black));
+
+        // Unchecked keeps the old behaviour
+        assert!(Game::from_sgf(sgf).is_ok());
+
+        // Only the first move can be played by white
+        let sgf = "(;GM[1]FF[4]SZ[9];W[ee];B[cc];B[gg];W[cg])";
+        match Game::from_sgf_with(sgf, SgfReplay::Strict) {
+            Err(SgfError::IllegalMove(diagnostic)) => {
+                assert_eq!(diagnostic.move_number, 3);
+                assert_eq!(diagnostic.error, PlayError::WrongColor);
+            }
+            other => panic!("expected a move out of turn, got {other:?}"),
+        }
+        let import = Game::from_sgf_with(sgf, SgfReplay::Lenient).unwrap();
+        assert_eq!(import.warnings.len(), 1);
+        assert!(import.warnings[0].skipped);
+        assert_eq!(import.game.record().len(), 3);
+        assert_eq!(import.game.goban().get_color((6, 6)), None);
+        let game = Game::from_sgf(sgf).unwrap();
+        assert_eq!(game.goban().get_color((6, 6)), Some(Color::Black));
+
+        let sgf = "(;GM[1]FF[4]SZ[9];B[ee];W[ee)";
+        assert!(matches!(
+            Game::from_sgf_with(sgf, SgfReplay::Strict),
+            Err(SgfError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn illegal_move_loses() {
+        let rule = Rule {
+            illegal_move_loses: true,
+            ..JAPANESE
+        };
+        let mut game = Game::new(GobanSizes::Nine, rule);
+        game.play(Move::Play(0, 1)); // B
+        game.play(Move::Play(0, 0)); // W
+        game.play(Move::Play(1, 0)); // B
+
+        assert!(game.try_play(Move::Play(9, 9)).is_err());
+        game.try_play(Move::Play(0, 0)).unwrap(); // W suicide
+        assert_eq!(game.goban().get_color((0, 0)), EMPTY);
+        assert_eq!(
+            game.outcome(),
+            Some(EndGame::WinnerByForfeit(Color::Black))
+        );
+        assert_eq!(game.try_play(Move::Pass).err(), Some(PlayError::GameOver));
+
+        let mut game = Game::new(GobanSizes::Nine, CHINESE);
+        game.forfeit(Color::Black);
+        assert_eq!(
+            game.outcome(),
+            Some(EndGame::WinnerByForfeit(Color::White))
+        );
+        // The result of a finished game stays
+        game.forfeit(Color::White);
+        assert_eq!(
+            game.outcome(),
+            Some(EndGame::WinnerByForfeit(Color::White))
+        );
+    }
+
     #[test]
     fn sgf_test() {
         let game = Game::from_sgf(include_str!("../sgf/ShusakuvsInseki.sgf")).unwrap();